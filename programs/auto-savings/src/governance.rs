@@ -0,0 +1,1152 @@
+//! Protocol administration: treasury config, admin council, roles, pausing, fee
+//! overrides and the sharded TVL stats
+
+use crate::*;
+
+pub(crate) fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    let treasury_config = &mut ctx.accounts.treasury_config;
+    treasury_config.authority = ctx.accounts.authority.key();
+    treasury_config.bump = ctx.bumps.treasury_config;
+    treasury_config.is_paused = false;
+    treasury_config.version = TREASURY_CONFIG_VERSION;
+    treasury_config.treasury_bump = ctx.bumps.treasury;
+    treasury_config.pause_mask = 0;
+    treasury_config.withdrawals_paused_until = 0;
+    treasury_config.total_tvl = 0;
+    treasury_config.unmigrated_tvl = 0;
+    treasury_config.tvl_cap = TVL_CAP_LAMPORTS;
+    treasury_config.fee_basis_points = PLATFORM_FEE_BASIS_POINTS as u16;
+    treasury_config.default_max_vault_balance = 0;
+    treasury_config.default_max_daily_deposit = 0;
+    treasury_config.onboarding_verifier = None;
+    treasury_config.allowlist_root = None;
+    treasury_config.achievement_uri_base = String::new();
+    log_msg!("Platform treasury initialized with 100 SOL TVL cap");
+    Ok(())
+}
+
+pub(crate) fn migrate_treasury_config(ctx: Context<MigrateTreasuryConfig>) -> Result<()> {
+    let info = ctx.accounts.treasury_config.to_account_info();
+    grow_account(
+        &info,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        8 + TreasuryConfig::INIT_SPACE,
+    )?;
+
+    let mut treasury_config = TreasuryConfig::try_deserialize(&mut &info.data.borrow()[..])?;
+    require!(
+        treasury_config.version < TREASURY_CONFIG_VERSION,
+        ErrorCode::AlreadyMigrated
+    );
+    if treasury_config.version == 0 {
+        // The launch layout only ever paused deposits
+        treasury_config.pause_mask = if treasury_config.is_paused {
+            PAUSE_DEPOSITS
+        } else {
+            0
+        };
+        treasury_config.is_paused = false;
+        treasury_config.treasury_bump = ctx.bumps.treasury;
+        treasury_config.fee_basis_points = PLATFORM_FEE_BASIS_POINTS as u16;
+        treasury_config.withdrawals_paused_until = 0;
+        // Launch-era deposits were only counted here; `migrate_user_config` moves
+        // each vault's balance into its stats shard
+        treasury_config.unmigrated_tvl = treasury_config.total_tvl;
+        treasury_config.default_max_vault_balance = 0;
+        treasury_config.default_max_daily_deposit = 0;
+        treasury_config.onboarding_verifier = None;
+        treasury_config.allowlist_root = None;
+        treasury_config.achievement_uri_base = String::new();
+    }
+    let from_version = treasury_config.version;
+    treasury_config.version = TREASURY_CONFIG_VERSION;
+    treasury_config.try_serialize(&mut &mut info.data.borrow_mut()[..])?;
+
+    log_msg!(
+        "TreasuryConfig migrated from v{} to v{}",
+        from_version,
+        TREASURY_CONFIG_VERSION
+    );
+    Ok(())
+}
+
+pub(crate) fn initialize_admin_council(
+    ctx: Context<InitializeAdminCouncil>,
+    members: Vec<Pubkey>,
+    threshold: u8,
+    timelock_seconds: i64,
+) -> Result<()> {
+    let admin_council = &mut ctx.accounts.admin_council;
+    admin_council.set_membership(members, threshold, timelock_seconds)?;
+    admin_council.proposal_count = 0;
+    admin_council.bump = ctx.bumps.admin_council;
+
+    // From here on every admin power flows through council proposals
+    ctx.accounts.treasury_config.authority = admin_council.key();
+    let roles = &mut ctx.accounts.roles;
+    roles.super_admin = admin_council.key();
+    roles.members = Vec::new();
+
+    log_msg!(
+        "Admin council initialized: {}-of-{}, {}s timelock",
+        threshold,
+        admin_council.members.len(),
+        timelock_seconds
+    );
+    Ok(())
+}
+
+pub(crate) fn propose_admin_action(
+    ctx: Context<ProposeAdminAction>,
+    action: AdminAction,
+) -> Result<()> {
+    let admin_council = &mut ctx.accounts.admin_council;
+    let proposer = ctx.accounts.proposer.key();
+    require!(admin_council.is_member(&proposer), ErrorCode::Unauthorized);
+
+    let now = Clock::get()?.unix_timestamp;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.id = admin_council.proposal_count;
+    proposal.proposer = proposer;
+    proposal.action = action;
+    proposal.approvals = vec![proposer];
+    proposal.created_at = now;
+    proposal.approved_at = 0;
+    proposal.executed = false;
+    proposal.bump = ctx.bumps.proposal;
+    if proposal.approvals.len() >= admin_council.threshold as usize {
+        proposal.approved_at = now;
+    }
+
+    admin_council.proposal_count = admin_council
+        .proposal_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    log_msg!("Admin proposal {} created", proposal.id);
+    Ok(())
+}
+
+pub(crate) fn approve_admin_action(ctx: Context<ApproveAdminAction>) -> Result<()> {
+    let admin_council = &ctx.accounts.admin_council;
+    let proposal = &mut ctx.accounts.proposal;
+    let approver = ctx.accounts.approver.key();
+
+    require!(admin_council.is_member(&approver), ErrorCode::Unauthorized);
+    require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+    require!(
+        !proposal.approvals.contains(&approver),
+        ErrorCode::AlreadyApproved
+    );
+
+    proposal.approvals.push(approver);
+    let approvals = proposal
+        .approvals
+        .iter()
+        .filter(|approver| admin_council.is_member(approver))
+        .count();
+    if proposal.approved_at == 0 && approvals >= admin_council.threshold as usize {
+        proposal.approved_at = Clock::get()?.unix_timestamp;
+    }
+
+    log_msg!(
+        "Admin proposal {} approved ({}/{})",
+        proposal.id,
+        proposal.approvals.len(),
+        admin_council.threshold
+    );
+    Ok(())
+}
+
+pub(crate) fn execute_admin_action(ctx: Context<ExecuteAdminAction>) -> Result<()> {
+    let admin_council = &ctx.accounts.admin_council;
+    let proposal = &mut ctx.accounts.proposal;
+
+    require!(
+        admin_council.is_member(&ctx.accounts.executor.key()),
+        ErrorCode::Unauthorized
+    );
+    require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+    let approvals = proposal
+        .approvals
+        .iter()
+        .filter(|approver| admin_council.is_member(approver))
+        .count();
+    require!(
+        approvals >= admin_council.threshold as usize,
+        ErrorCode::ThresholdNotMet
+    );
+
+    // Emergency pauses skip the timelock so incidents can be contained immediately
+    let now = Clock::get()?.unix_timestamp;
+    let delay = if proposal
+        .action
+        .is_emergency(ctx.accounts.treasury_config.active_pause_mask(now))
+    {
+        0
+    } else {
+        admin_council.timelock_seconds
+    };
+    let executable_at = proposal
+        .approved_at
+        .checked_add(delay)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(now >= executable_at, ErrorCode::TimelockNotElapsed);
+
+    let treasury_config = &mut ctx.accounts.treasury_config;
+    match proposal.action.clone() {
+        AdminAction::SetPauseMask {
+            pause_mask,
+            withdrawal_pause_seconds,
+        } => {
+            treasury_config.set_pause_mask(pause_mask, withdrawal_pause_seconds, now)?;
+        }
+        AdminAction::SetTvlCap { new_cap } => {
+            treasury_config.set_tvl_cap(new_cap)?;
+        }
+        AdminAction::SetFeeBasisPoints { fee_basis_points } => {
+            treasury_config.set_fee_basis_points(fee_basis_points)?;
+        }
+        AdminAction::SetSuperAdmin { super_admin } => {
+            let roles = ctx
+                .accounts
+                .roles
+                .as_mut()
+                .ok_or(ErrorCode::MissingRolesAccount)?;
+            roles.super_admin = super_admin;
+            log_msg!("Super admin set to {}", super_admin);
+        }
+        AdminAction::SetCouncil {
+            members,
+            threshold,
+            timelock_seconds,
+        } => {
+            ctx.accounts
+                .admin_council
+                .set_membership(members, threshold, timelock_seconds)?;
+            log_msg!(
+                "Admin council set to {}-of-{}, {}s timelock",
+                threshold,
+                ctx.accounts.admin_council.members.len(),
+                timelock_seconds
+            );
+        }
+        AdminAction::SetRoles { member, role_flags } => {
+            ctx.accounts
+                .roles
+                .as_mut()
+                .ok_or(ErrorCode::MissingRolesAccount)?
+                .set_member_roles(member, role_flags)?;
+        }
+        AdminAction::WithdrawTreasury {
+            amount,
+            destination,
+        } => {
+            require!(
+                ctx.accounts.destination.key() == destination,
+                ErrorCode::InvalidDestination
+            );
+            withdraw_from_treasury(
+                &ctx.accounts.treasury,
+                &ctx.accounts.destination,
+                &ctx.accounts.system_program,
+                ctx.accounts.treasury_config.treasury_bump,
+                amount,
+            )?;
+        }
+    }
+    proposal.executed = true;
+
+    log_msg!("Admin proposal {} executed", proposal.id);
+    Ok(())
+}
+
+pub(crate) fn initialize_roles(ctx: Context<InitializeRoles>) -> Result<()> {
+    let super_admin = ctx.accounts.treasury_config.authority;
+    let roles = &mut ctx.accounts.roles;
+    roles.super_admin = super_admin;
+    roles.members = Vec::new();
+    roles.bump = ctx.bumps.roles;
+
+    log_msg!("Roles initialized with super admin {}", super_admin);
+    Ok(())
+}
+
+pub(crate) fn set_roles(ctx: Context<SetRoles>, member: Pubkey, role_flags: u8) -> Result<()> {
+    let roles = &mut ctx.accounts.roles;
+    require!(
+        roles.super_admin == ctx.accounts.super_admin.key(),
+        ErrorCode::Unauthorized
+    );
+    roles.set_member_roles(member, role_flags)
+}
+
+pub(crate) fn transfer_super_admin(ctx: Context<SetRoles>, new_super_admin: Pubkey) -> Result<()> {
+    let roles = &mut ctx.accounts.roles;
+    require!(
+        roles.super_admin == ctx.accounts.super_admin.key(),
+        ErrorCode::Unauthorized
+    );
+    roles.super_admin = new_super_admin;
+
+    log_msg!("Super admin transferred to {}", new_super_admin);
+    Ok(())
+}
+
+pub(crate) fn set_pause_mask(
+    ctx: Context<RoleGatedAdmin>,
+    pause_mask: u8,
+    withdrawal_pause_seconds: i64,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_PAUSER)?;
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts
+        .treasury_config
+        .set_pause_mask(pause_mask, withdrawal_pause_seconds, now)
+}
+
+pub(crate) fn update_tvl_cap(ctx: Context<RoleGatedAdmin>, new_cap: u64) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    ctx.accounts.treasury_config.set_tvl_cap(new_cap)
+}
+
+pub(crate) fn update_fee(ctx: Context<RoleGatedAdmin>, fee_basis_points: u16) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    ctx.accounts
+        .treasury_config
+        .set_fee_basis_points(fee_basis_points)
+}
+
+pub(crate) fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+    let (admin_council, _) = Pubkey::find_program_address(&[b"admin_council"], ctx.program_id);
+    require!(
+        ctx.accounts.treasury_config.authority != admin_council,
+        ErrorCode::CouncilGoverned
+    );
+    withdraw_from_treasury(
+        &ctx.accounts.treasury,
+        &ctx.accounts.destination,
+        &ctx.accounts.system_program,
+        ctx.bumps.treasury,
+        amount,
+    )
+}
+
+pub(crate) fn withdraw_treasury_tokens<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawTreasuryTokens<'info>>,
+    amount: u64,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+    let (admin_council, _) = Pubkey::find_program_address(&[b"admin_council"], ctx.program_id);
+    require!(
+        ctx.accounts.treasury_config.authority != admin_council,
+        ErrorCode::CouncilGoverned
+    );
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        amount <= ctx.accounts.treasury_token_account.amount,
+        ErrorCode::InsufficientFunds
+    );
+
+    let seeds: &[&[u8]] = &[
+        b"treasury_vault".as_ref(),
+        &[ctx.accounts.treasury_config.treasury_bump],
+    ];
+    invoke_transfer_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.treasury_token_account.to_account_info(),
+        ctx.accounts.mint.to_account_info(),
+        ctx.accounts.destination_token_account.to_account_info(),
+        ctx.accounts.treasury.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+        ctx.accounts.mint.decimals,
+        &[seeds],
+    )?;
+
+    log_msg!(
+        "Withdrew {} of mint {} from treasury",
+        amount,
+        ctx.accounts.mint.key()
+    );
+    Ok(())
+}
+
+pub(crate) fn set_default_user_limits(
+    ctx: Context<RoleGatedAdmin>,
+    max_vault_balance: u64,
+    max_daily_deposit: u64,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    let treasury_config = &mut ctx.accounts.treasury_config;
+    treasury_config.default_max_vault_balance = max_vault_balance;
+    treasury_config.default_max_daily_deposit = max_daily_deposit;
+
+    log_msg!(
+        "Default user limits: {} lamports max balance, {} lamports per day",
+        max_vault_balance,
+        max_daily_deposit
+    );
+    Ok(())
+}
+
+pub(crate) fn set_user_limits(
+    ctx: Context<ManageUser>,
+    max_vault_balance: Option<u64>,
+    max_daily_deposit: Option<u64>,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.max_vault_balance_override = max_vault_balance;
+    user_config.max_daily_deposit_override = max_daily_deposit;
+
+    log_msg!(
+        "Limits for {} overridden: max balance {:?}, daily {:?}",
+        user_config.owner,
+        max_vault_balance,
+        max_daily_deposit
+    );
+    Ok(())
+}
+
+pub(crate) fn set_onboarding_gate(
+    ctx: Context<RoleGatedAdmin>,
+    verifier: Option<Pubkey>,
+    allowlist_root: Option<[u8; 32]>,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    let treasury_config = &mut ctx.accounts.treasury_config;
+    treasury_config.onboarding_verifier = verifier;
+    treasury_config.allowlist_root = allowlist_root;
+
+    log_msg!(
+        "Onboarding gate: verifier {:?}, allowlist {}",
+        verifier,
+        if allowlist_root.is_some() {
+            "set"
+        } else {
+            "none"
+        }
+    );
+    Ok(())
+}
+
+pub(crate) fn set_achievement_uri_base(
+    ctx: Context<RoleGatedAdmin>,
+    uri_base: String,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    require!(
+        uri_base.len() <= MAX_ACHIEVEMENT_URI_BASE_LEN,
+        ErrorCode::AchievementUriTooLong
+    );
+    log_msg!("Achievement metadata URI base set to {}", uri_base);
+    ctx.accounts.treasury_config.achievement_uri_base = uri_base;
+    Ok(())
+}
+
+pub(crate) fn freeze_user(ctx: Context<ManageUser>, recovery_destination: Pubkey) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    ctx.accounts.roles.require_role(&admin, ROLE_RISK_MANAGER)?;
+    require!(
+        recovery_destination != Pubkey::default(),
+        ErrorCode::InvalidDestination
+    );
+    let user_config = &mut ctx.accounts.user_config;
+    require!(
+        user_config.status != UserStatus::Recovered,
+        ErrorCode::AccountRecovered
+    );
+    user_config.status = UserStatus::Frozen;
+    user_config.recovery_destination = recovery_destination;
+    let owner = user_config.owner;
+    set_owner_shares_frozen(ctx.accounts, true)?;
+
+    emit!(UserFrozen {
+        owner,
+        recovery_destination,
+        frozen_by: admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    log_msg!("User {} frozen", owner);
+    Ok(())
+}
+
+pub(crate) fn unfreeze_user(ctx: Context<ManageUser>) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    ctx.accounts.roles.require_role(&admin, ROLE_RISK_MANAGER)?;
+    let user_config = &mut ctx.accounts.user_config;
+    require!(
+        user_config.status == UserStatus::Frozen,
+        ErrorCode::AccountNotFrozen
+    );
+    user_config.status = UserStatus::Active;
+    user_config.recovery_destination = Pubkey::default();
+    let owner = user_config.owner;
+    set_owner_shares_frozen(ctx.accounts, false)?;
+
+    emit!(UserUnfrozen {
+        owner,
+        unfrozen_by: admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    log_msg!("User {} unfrozen", owner);
+    Ok(())
+}
+
+pub(crate) fn initialize_stats_shard(ctx: Context<InitializeStatsShard>, index: u8) -> Result<()> {
+    require!(index < STATS_SHARD_COUNT, ErrorCode::InvalidStatsShard);
+    let mut stats_shard = ctx.accounts.stats_shard.load_init()?;
+    stats_shard.index = index;
+    stats_shard.bump = ctx.bumps.stats_shard;
+
+    log_msg!("Stats shard {} initialized", index);
+    Ok(())
+}
+
+pub(crate) fn aggregate_stats<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AggregateStats<'info>>,
+) -> Result<()> {
+    require!(
+        ctx.remaining_accounts.len() == STATS_SHARD_COUNT as usize,
+        ErrorCode::InvalidStatsShard
+    );
+
+    let mut stats_shards = Vec::with_capacity(STATS_SHARD_COUNT as usize);
+    let mut total_tvl = ctx.accounts.treasury_config.unmigrated_tvl;
+    for (index, shard_info) in ctx.remaining_accounts.iter().enumerate() {
+        let loader = load_stats_shard(shard_info, index as u8, ctx.program_id)?;
+        total_tvl = total_tvl
+            .checked_add(loader.load()?.total_tvl)
+            .ok_or(ErrorCode::Overflow)?;
+        stats_shards.push(loader);
+    }
+
+    // The pool only gets a slot once it exists
+    let share_pool_info = ctx.accounts.share_pool.to_account_info();
+    let mut share_pool = if share_pool_info.owner == ctx.program_id {
+        let share_pool = SharePool::try_deserialize(&mut &share_pool_info.data.borrow()[..])?;
+        total_tvl = total_tvl
+            .checked_add(share_pool.tvl()?)
+            .ok_or(ErrorCode::Overflow)?;
+        Some(share_pool)
+    } else {
+        None
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let treasury_config = &mut ctx.accounts.treasury_config;
+    let slots = stats_shards.len() as u64 + share_pool.is_some() as u64;
+    let headroom = treasury_config.tvl_cap.saturating_sub(total_tvl) / slots;
+    for loader in &stats_shards {
+        let mut stats_shard = loader.load_mut()?;
+        stats_shard.tvl_quota = stats_shard
+            .total_tvl
+            .checked_add(headroom)
+            .ok_or(ErrorCode::Overflow)?;
+        if let Some(reward_pool) = ctx.accounts.reward_pool.as_mut() {
+            stats_shard.settle_rewards(reward_pool, now)?;
+        }
+    }
+    if let Some(share_pool) = share_pool.as_mut() {
+        share_pool.tvl_quota = share_pool
+            .tvl()?
+            .checked_add(headroom)
+            .ok_or(ErrorCode::Overflow)?;
+        share_pool.try_serialize(&mut &mut share_pool_info.data.borrow_mut()[..])?;
+    }
+    treasury_config.total_tvl = total_tvl;
+
+    log_msg!(
+        "Aggregated TVL: {} lamports, {} lamports of quota added per slot",
+        total_tvl,
+        headroom
+    );
+    Ok(())
+}
+
+pub(crate) fn set_fee_override(
+    ctx: Context<SetFeeOverride>,
+    subject: Pubkey,
+    fee_basis_points: u16,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+    require!(
+        fee_basis_points <= MAX_FEE_BASIS_POINTS,
+        ErrorCode::FeeTooHigh
+    );
+
+    let fee_override = &mut ctx.accounts.fee_override;
+    fee_override.subject = subject;
+    fee_override.fee_basis_points = fee_basis_points;
+    fee_override.bump = ctx.bumps.fee_override;
+
+    log_msg!(
+        "Fee override for {} set to {} bps",
+        subject,
+        fee_basis_points
+    );
+    Ok(())
+}
+
+pub(crate) fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+
+    log_msg!(
+        "Fee override for {} removed",
+        ctx.accounts.fee_override.subject
+    );
+    Ok(())
+}
+
+/// Platform fee in lamports for `amount` at the given basis points
+pub(crate) fn calculate_fee(amount: u64, fee_basis_points: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_basis_points as u128)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(fee as u64)
+}
+
+/// Move collected fees out of the treasury PDA, keeping it rent-exempt
+pub(crate) fn withdraw_from_treasury<'info>(
+    treasury: &SystemAccount<'info>,
+    destination: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    treasury_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let available = treasury
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    require!(amount <= available, ErrorCode::InsufficientFunds);
+
+    transfer_from_pda(
+        treasury,
+        destination.to_account_info(),
+        system_program,
+        &[b"treasury_vault".as_ref(), &[treasury_bump]],
+        amount,
+    )?;
+
+    log_msg!("Withdrew {} lamports from treasury", amount);
+    Ok(())
+}
+
+/// Stats shard for a vault owner. Pubkeys are already uniformly distributed, so the
+/// first byte is as good as a hash.
+pub(crate) fn stats_shard_index(owner: &Pubkey) -> u8 {
+    owner.as_ref()[0] % STATS_SHARD_COUNT
+}
+
+/// Load a stats shard passed as a remaining account, checking it is shard `index`
+pub(crate) fn load_stats_shard<'info>(
+    shard_info: &'info AccountInfo<'info>,
+    index: u8,
+    program_id: &Pubkey,
+) -> Result<AccountLoader<'info, StatsShard>> {
+    let loader = AccountLoader::<StatsShard>::try_from(shard_info)?;
+    {
+        let stats_shard = loader.load()?;
+        let expected = Pubkey::create_program_address(
+            &[b"stats_shard", &[index], &[stats_shard.bump]],
+            program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidStatsShard)?;
+        require!(
+            shard_info.key() == expected && stats_shard.index == index,
+            ErrorCode::InvalidStatsShard
+        );
+    }
+    Ok(loader)
+}
+
+/// Fee rate for a deposit or withdrawal by `user`: a `FeeOverride` for the user, or
+/// for the partner program that invoked us directly, replaces the protocol rate.
+/// The instructions sysvar only shows top-level instructions, so a partner rate
+/// needs the partner's instruction to be top-level and us one CPI below it; a
+/// deeper call could have come through any program the partner invoked.
+pub(crate) fn fee_basis_points_for(
+    treasury_config: &TreasuryConfig,
+    fee_rate: &FeeRate,
+    user: &Pubkey,
+) -> Result<u16> {
+    let Some(fee_override) = &fee_rate.fee_override else {
+        return Ok(treasury_config.fee_basis_points);
+    };
+    if fee_override.subject != *user {
+        let instructions = fee_rate
+            .instructions
+            .as_ref()
+            .ok_or(ErrorCode::InvalidFeeOverride)?;
+        require!(
+            get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+            ErrorCode::InvalidFeeOverride
+        );
+        let caller = get_instruction_relative(0, &instructions.to_account_info())?.program_id;
+        require!(
+            caller != crate::ID && caller == fee_override.subject,
+            ErrorCode::InvalidFeeOverride
+        );
+    }
+    Ok(fee_override.fee_basis_points)
+}
+
+// Account Structures
+/// Optional negotiated fee rate, shared by every instruction that charges a fee
+#[derive(Accounts)]
+pub struct FeeRate<'info> {
+    #[account(
+        seeds = [b"fee_override", fee_override.subject.as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Option<Account<'info, FeeOverride>>,
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: Instructions sysvar, needed to apply a partner program's fee override
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TreasuryConfig::INIT_SPACE,
+        seeds = [b"treasury_config"],
+        bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        seeds = [b"treasury_vault"],
+        bump
+    )]
+    /// CHECK: Treasury PDA for collecting fees
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTreasuryConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury_config"],
+        bump
+    )]
+    /// CHECK: May still be in an older layout; decoded after it is grown
+    pub treasury_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_vault"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdminCouncil<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AdminCouncil::INIT_SPACE,
+        seeds = [b"admin_council"],
+        bump
+    )]
+    pub admin_council: Account<'info, AdminCouncil>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdminAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"admin_council"],
+        bump = admin_council.bump
+    )]
+    pub admin_council: Account<'info, AdminCouncil>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + AdminProposal::INIT_SPACE,
+        seeds = [
+            b"admin_proposal",
+            admin_council.proposal_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAdminAction<'info> {
+    #[account(
+        seeds = [b"admin_council"],
+        bump = admin_council.bump
+    )]
+    pub admin_council: Account<'info, AdminCouncil>,
+
+    #[account(
+        mut,
+        seeds = [b"admin_proposal", proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAdminAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"admin_council"],
+        bump = admin_council.bump
+    )]
+    pub admin_council: Account<'info, AdminCouncil>,
+
+    #[account(
+        mut,
+        seeds = [b"admin_proposal", proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Only used by treasury withdrawals, checked against the proposal
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    /// Only required when the proposal changes roles or the super admin
+    #[account(
+        mut,
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Option<Account<'info, Roles>>,
+
+    pub executor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRoles<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Roles::INIT_SPACE,
+        seeds = [b"roles"],
+        bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    #[account(
+        mut,
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    pub super_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RoleGatedAdmin<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Any account chosen by the treasurer to receive fees
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryTokens<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA, authority of the treasury token account
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_token", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Any token account chosen by the treasurer to receive fees
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ManageUser<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        mut,
+        seeds = [b"user_config", user_config.owner.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    /// Share pool accounts; pass them to freeze or thaw the owner's share tokens too
+    #[account(
+        seeds = [b"share_pool"],
+        bump
+    )]
+    pub share_pool: Option<Account<'info, SharePool>>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump
+    )]
+    pub share_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = user_config.owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_shares: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct InitializeStatsShard<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<StatsShard>(),
+        seeds = [b"stats_shard", [index].as_ref()],
+        bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AggregateStats<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump
+    )]
+    /// CHECK: Counted once initialized; the seeds pin the address so it can't be left out
+    pub share_pool: UncheckedAccount<'info>,
+
+    /// Settles every shard's rewards when passed
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Option<Account<'info, RewardPool>>,
+}
+
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct SetFeeOverride<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + FeeOverride::INIT_SPACE,
+        seeds = [b"fee_override", subject.as_ref()],
+        bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeOverride<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_override", fee_override.subject.as_ref()],
+        bump = fee_override.bump,
+        close = admin
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
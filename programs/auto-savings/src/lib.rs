@@ -1,12 +1,147 @@
 use anchor_lang::prelude::*;
-use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{
+    get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT,
+};
+use anchor_lang::solana_program::keccak::hashv;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
+use anchor_lang::system_program::{create_account, transfer, CreateAccount, Transfer};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    self, Burn, CloseAccount, FreezeAccount, Mint, MintTo, ThawAccount, TokenAccount,
+    TokenInterface,
+};
+use spl_token_2022::extension::{metadata_pointer, ExtensionType};
+use spl_token_2022::instruction::{
+    initialize_mint2, initialize_non_transferable_mint, mint_to, set_authority, AuthorityType,
+};
+use spl_token_2022::onchain::invoke_transfer_checked;
+use spl_token_metadata_interface::state::TokenMetadata;
 
 declare_id!("FoPp8w9H2MFskx77ypu5yyxizKLDqtPSZ7dMvPs4whGn");
 
+// Savings-hook program; `settle_hook_savings` reports what it moved back to it
+mod savings_hook_program {
+    use super::*;
+    declare_id!("E6PnXnUhFphvBYDrWENCWZXaTunKG94MNScjKVkPqmL9");
+}
+
 // Platform fee: 0.4% (40 basis points out of 10,000)
 const PLATFORM_FEE_BASIS_POINTS: u64 = 40;
 const BASIS_POINTS_DIVISOR: u64 = 10000;
 const TVL_CAP_LAMPORTS: u64 = 100_000_000_000; // 100 SOL cap
+const MAX_FEE_BASIS_POINTS: u16 = 500; // 5% hard ceiling for admin fee changes
+
+// Current `TreasuryConfig` layout; older accounts are upgraded by `migrate_treasury_config`
+const TREASURY_CONFIG_VERSION: u8 = 1;
+
+// Current `UserConfig` layout; older accounts are upgraded by `migrate_user_config`
+const USER_CONFIG_VERSION: u8 = 1;
+
+// Admin council limits
+const MAX_COUNCIL_MEMBERS: usize = 10;
+
+// Admin roles (bitflags stored per `RoleMember`)
+const ROLE_PAUSER: u8 = 1 << 0;
+const ROLE_RISK_MANAGER: u8 = 1 << 1;
+const ROLE_TREASURER: u8 = 1 << 2;
+const ALL_ROLES: u8 = ROLE_PAUSER | ROLE_RISK_MANAGER | ROLE_TREASURER;
+const MAX_ROLE_MEMBERS: usize = 16;
+
+// Pause flags (bitflags stored in `TreasuryConfig::pause_mask`)
+const PAUSE_DEPOSITS: u8 = 1 << 0;
+const PAUSE_WITHDRAWALS: u8 = 1 << 1;
+const PAUSE_NEW_USERS: u8 = 1 << 2;
+const PAUSE_SWAPS: u8 = 1 << 3; // personal vault <-> pool share conversions
+const PAUSE_SCHEDULES: u8 = 1 << 4;
+const ALL_PAUSE_FLAGS: u8 =
+    PAUSE_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_NEW_USERS | PAUSE_SWAPS | PAUSE_SCHEDULES;
+// User withdrawals can never be frozen for longer than this
+const MAX_WITHDRAWAL_PAUSE_SECONDS: i64 = 72 * 60 * 60; // 3 days
+
+// Minimum gap after a withdrawal pause expires before it can be re-applied
+const WITHDRAWAL_PAUSE_COOLDOWN_SECONDS: i64 = 24 * 60 * 60; // 1 day
+
+// Social recovery limits
+const MAX_RECOVERY_GUARDIANS: usize = 5;
+const MIN_RECOVERY_DELAY_SECONDS: i64 = 24 * 60 * 60; // owner gets at least a day to cancel
+
+// Inheritance: shortest inactivity window a beneficiary can be configured with
+const MIN_INACTIVITY_PERIOD_SECONDS: i64 = 30 * 24 * 60 * 60; // 30 days
+
+// Per-user velocity controls: daily deposit limits are tracked over this window
+const DEPOSIT_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+// TVL is tracked across this many `StatsShard` PDAs so deposits from different users
+// don't all write-lock `TreasuryConfig`. Each shard (and the share pool) holds a quota of
+// the TVL cap that `aggregate_stats` rebalances.
+const STATS_SHARD_COUNT: u8 = 8;
+
+// Savings streaks: a streak counts consecutive periods with at least one deposit
+const STREAK_PERIOD_SECONDS: i64 = 24 * 60 * 60;
+// Smallest deposit (after fee) that counts toward a streak. Its fee outweighs the most
+// fee credit one period's points can redeem for, so dust deposits can't farm points.
+const MIN_STREAK_DEPOSIT_LAMPORTS: u64 = LAMPORTS_PER_SOL / 10;
+// Points earned per streak period, times the streak length up to the multiplier cap
+const POINTS_PER_STREAK_PERIOD: u64 = 10;
+const MAX_STREAK_MULTIPLIER: u64 = 30;
+// Fee credit granted per redeemed point
+const LAMPORTS_PER_POINT: u64 = 1_000;
+
+// Reward distribution: reward-per-share is scaled by this to keep precision
+const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+// Share pool: the share mint uses SOL's decimals so 1 share starts at 1 lamport
+const SHARE_DECIMALS: u8 = 9;
+
+// Longest notice period the risk role can impose on queued pooled withdrawals
+const MAX_WITHDRAWAL_NOTICE_SECONDS: i64 = 30 * 24 * 60 * 60; // 30 days
+
+// Achievement NFTs
+const ACHIEVEMENT_SYMBOL: &str = "SAVE";
+// Metadata for each badge lives at `{achievement_uri_base}{slug}.json`
+const MAX_ACHIEVEMENT_URI_BASE_LEN: usize = 128;
+// Smallest savings goal that can earn the goal-reached badge
+const MIN_SAVINGS_GOAL_LAMPORTS: u64 = LAMPORTS_PER_SOL / 10;
+const ACHIEVEMENT_STREAK_PERIODS: u32 = 30; // 30-day streak badge
+
+// Longest memo accepted by `deposit_for`
+const MAX_MEMO_LEN: usize = 64;
+
+// Sponsors can match at most 1:1
+const MAX_MATCH_BASIS_POINTS: u16 = 10_000;
+
+// Joint vault limits
+const MAX_JOINT_MEMBERS: usize = 8;
+
+// Savings circle (ROSCA) limits
+const MAX_CIRCLE_MEMBERS: usize = 12;
+const MAX_LATE_PENALTY_BASIS_POINTS: u16 = 1000; // 10% of one contribution
+
+/// `msg!` that only runs with the `verbose-logs` feature. Log formatting is one of the
+/// larger compute costs per instruction, so release builds leave it out.
+macro_rules! log_msg {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logs") {
+            msg!($($arg)*);
+        }
+    };
+}
+
+pub mod governance;
+pub mod pool;
+pub mod state;
+pub mod tokens;
+pub mod vaults;
+
+pub use governance::*;
+pub use pool::*;
+pub use state::*;
+pub use tokens::*;
+pub use vaults::*;
 
 #[program]
 pub mod auto_savings {
@@ -14,308 +149,686 @@ pub mod auto_savings {
 
     /// Initialize the platform treasury (one-time, protocol operator only)
     pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
-        let treasury_config = &mut ctx.accounts.treasury_config;
-        treasury_config.authority = ctx.accounts.authority.key();
-        treasury_config.bump = ctx.bumps.treasury_config;
-        treasury_config.is_paused = false;
-        treasury_config.total_tvl = 0;
-        treasury_config.tvl_cap = TVL_CAP_LAMPORTS;
-        msg!("Platform treasury initialized with 100 SOL TVL cap");
-        Ok(())
+        governance::initialize_treasury(ctx)
+    }
+
+    /// Permissionless: grow a `TreasuryConfig` written by an older program version to
+    /// the current layout and fill the appended fields with their defaults. The payer
+    /// covers the extra rent.
+    pub fn migrate_treasury_config(ctx: Context<MigrateTreasuryConfig>) -> Result<()> {
+        governance::migrate_treasury_config(ctx)
     }
 
     /// Initialize a user's savings account and vault
-    pub fn initialize_user(ctx: Context<InitializeUser>) -> Result<()> {
-        let user_config = &mut ctx.accounts.user_config;
-        user_config.owner = ctx.accounts.user.key();
-        user_config.bump = ctx.bumps.user_config;
-        user_config.vault_bump = ctx.bumps.vault;
+    /// While onboarding is gated, the user needs either a co-signature from the
+    /// registered verifier or a Merkle proof of membership in the allowlist root
+    pub fn initialize_user(
+        ctx: Context<InitializeUser>,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        vaults::initialize_user(ctx, allowlist_proof)
+    }
 
-        msg!("User initialized");
-        Ok(())
+    /// Permissionless: grow a `UserConfig` written by an older program version to the
+    /// current layout and fill the appended fields with their defaults, so that anyone
+    /// acting for the owner (the owner, a beneficiary, a guardian) can unblock the vault.
+    /// The payer covers the extra rent.
+    pub fn migrate_user_config(ctx: Context<MigrateUserConfig>) -> Result<()> {
+        vaults::migrate_user_config(ctx)
     }
 
     /// Manually deposit SOL into the savings vault
+    ///
+    /// Compute budget: 15,000 CU (enforced by tests/compute-units.ts). The fee and
+    /// principal go to different accounts, so they stay two system transfers; the fee
+    /// transfer is skipped when it rounds to zero.
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        require!(amount > 0, ErrorCode::InvalidAmount);
-
-        let treasury_config = &mut ctx.accounts.treasury_config;
-        require!(!treasury_config.is_paused, ErrorCode::ProtocolPaused);
-
-        let new_tvl = treasury_config
-            .total_tvl
-            .checked_add(amount)
-            .ok_or(ErrorCode::Overflow)?;
-        require!(
-            new_tvl <= treasury_config.tvl_cap,
-            ErrorCode::TvlCapExceeded
-        );
-
-        // Calculate platform fee (0.4%)
-        let platform_fee = (amount as u128)
-            .checked_mul(PLATFORM_FEE_BASIS_POINTS as u128)
-            .ok_or(ErrorCode::Overflow)?
-            .checked_div(BASIS_POINTS_DIVISOR as u128)
-            .ok_or(ErrorCode::Overflow)? as u64;
-
-        let amount_after_fee = amount
-            .checked_sub(platform_fee)
-            .ok_or(ErrorCode::Overflow)?;
-
-        // Transfer fee to treasury
-        if platform_fee > 0 {
-            let fee_transfer = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user.to_account_info(),
-                    to: ctx.accounts.treasury.to_account_info(),
-                },
-            );
-            transfer(fee_transfer, platform_fee)?;
-        }
-
-        // Transfer remaining SOL to vault
-        let vault_transfer = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
-            },
-        );
-        transfer(vault_transfer, amount_after_fee)?;
-
-        // Update global TVL
-        treasury_config.total_tvl = treasury_config
-            .total_tvl
-            .checked_add(amount_after_fee)
-            .ok_or(ErrorCode::Overflow)?;
-
-        msg!(
-            "Deposited {} lamports to vault (fee: {} lamports)",
-            amount_after_fee,
-            platform_fee
-        );
-        Ok(())
+        vaults::deposit(ctx, amount)
     }
 
     /// Withdraw SOL from the savings vault
+    ///
+    /// Compute budget: 15,000 CU (enforced by tests/compute-units.ts)
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-        require!(amount > 0, ErrorCode::InvalidAmount);
-
-        let user_config = &ctx.accounts.user_config;
-        let treasury_config = &mut ctx.accounts.treasury_config;
-
-        // Calculate platform fee (0.4%)
-        let platform_fee = (amount as u128)
-            .checked_mul(PLATFORM_FEE_BASIS_POINTS as u128)
-            .ok_or(ErrorCode::Overflow)?
-            .checked_div(BASIS_POINTS_DIVISOR as u128)
-            .ok_or(ErrorCode::Overflow)? as u64;
-
-        let total_needed = amount
-            .checked_add(platform_fee)
-            .ok_or(ErrorCode::Overflow)?;
-
-        // Check vault has sufficient balance
-        let vault_balance = ctx.accounts.vault.lamports();
-        require!(vault_balance >= total_needed, ErrorCode::InsufficientFunds);
-
-        // Create signer seeds for vault PDA
-        let user_key = ctx.accounts.user.key();
-        let seeds: &[&[u8]] = &[
-            b"vault".as_ref(),
-            user_key.as_ref(),
-            &[user_config.vault_bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        // Transfer from vault to user
-        let user_transfer = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.vault.to_account_info(),
-                to: ctx.accounts.user.to_account_info(),
-            },
-            signer_seeds,
-        );
-        transfer(user_transfer, amount)?;
-
-        // Transfer fee to treasury
-        if platform_fee > 0 {
-            let fee_transfer = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.treasury.to_account_info(),
-                },
-                signer_seeds,
-            );
-            transfer(fee_transfer, platform_fee)?;
-        }
+        vaults::withdraw(ctx, amount)
+    }
 
-        // Update global TVL
-        treasury_config.total_tvl = treasury_config
-            .total_tvl
-            .checked_sub(amount)
-            .ok_or(ErrorCode::Overflow)?;
+    /// Create the M-of-N admin council and hand treasury authority and the super admin
+    /// seat over to it. Roles granted before the council existed are revoked; the
+    /// council re-grants them through `AdminAction::SetRoles`.
+    pub fn initialize_admin_council(
+        ctx: Context<InitializeAdminCouncil>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+        timelock_seconds: i64,
+    ) -> Result<()> {
+        governance::initialize_admin_council(ctx, members, threshold, timelock_seconds)
+    }
 
-        msg!(
-            "Withdrawn {} lamports from vault (fee: {} lamports)",
-            amount,
-            platform_fee
-        );
-        Ok(())
+    /// Propose an admin action (council members only, counts as the first approval)
+    pub fn propose_admin_action(
+        ctx: Context<ProposeAdminAction>,
+        action: AdminAction,
+    ) -> Result<()> {
+        governance::propose_admin_action(ctx, action)
     }
-}
 
-// Account Structures
-#[derive(Accounts)]
-pub struct InitializeTreasury<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + TreasuryConfig::INIT_SPACE,
-        seeds = [b"treasury_config"],
-        bump
-    )]
-    pub treasury_config: Account<'info, TreasuryConfig>,
-
-    #[account(
-        seeds = [b"treasury_vault"],
-        bump
-    )]
-    /// CHECK: Treasury PDA for collecting fees
-    pub treasury: SystemAccount<'info>,
-
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Approve a pending admin proposal (council members only)
+    pub fn approve_admin_action(ctx: Context<ApproveAdminAction>) -> Result<()> {
+        governance::approve_admin_action(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct InitializeUser<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + UserConfig::INIT_SPACE,
-        seeds = [b"user_config", user.key().as_ref()],
-        bump
-    )]
-    pub user_config: Account<'info, UserConfig>,
-
-    #[account(
-        seeds = [b"vault", user.key().as_ref()],
-        bump
-    )]
-    /// CHECK: User's savings vault PDA
-    pub vault: SystemAccount<'info>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Execute an approved admin proposal once its timelock has elapsed. Only
+    /// approvals from current members count, so a membership change can't be
+    /// bypassed with approvals from removed members.
+    pub fn execute_admin_action(ctx: Context<ExecuteAdminAction>) -> Result<()> {
+        governance::execute_admin_action(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct Deposit<'info> {
-    #[account(
-        seeds = [b"user_config", user.key().as_ref()],
-        bump = user_config.bump,
-        has_one = owner @ ErrorCode::Unauthorized
-    )]
-    pub user_config: Account<'info, UserConfig>,
-
-    #[account(
-        mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = user_config.vault_bump
-    )]
-    /// CHECK: User's savings vault PDA
-    pub vault: SystemAccount<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"treasury_config"],
-        bump = treasury_config.bump
-    )]
-    pub treasury_config: Account<'info, TreasuryConfig>,
-
-    #[account(
-        mut,
-        seeds = [b"treasury_vault"],
-        bump
-    )]
-    /// CHECK: Treasury PDA
-    pub treasury: SystemAccount<'info>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
-    /// CHECK: Owner field in user_config
-    pub owner: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Create the roles registry with the treasury authority (a wallet, or the admin
+    /// council once it exists) as super admin; anyone may pay for it
+    pub fn initialize_roles(ctx: Context<InitializeRoles>) -> Result<()> {
+        governance::initialize_roles(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(
-        seeds = [b"user_config", user.key().as_ref()],
-        bump = user_config.bump,
-        has_one = owner @ ErrorCode::Unauthorized
-    )]
-    pub user_config: Account<'info, UserConfig>,
-
-    #[account(
-        mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = user_config.vault_bump
-    )]
-    /// CHECK: User's savings vault PDA
-    pub vault: SystemAccount<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"treasury_config"],
-        bump = treasury_config.bump
-    )]
-    pub treasury_config: Account<'info, TreasuryConfig>,
-
-    #[account(
-        mut,
-        seeds = [b"treasury_vault"],
-        bump
-    )]
-    /// CHECK: Treasury PDA
-    pub treasury: SystemAccount<'info>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
-    /// CHECK: Owner field in user_config
-    pub owner: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Grant or replace a member's roles; passing 0 removes them (super admin only).
+    /// Once the admin council is super admin this goes through `AdminAction::SetRoles`.
+    pub fn set_roles(ctx: Context<SetRoles>, member: Pubkey, role_flags: u8) -> Result<()> {
+        governance::set_roles(ctx, member, role_flags)
+    }
 
-// Data Structures
-#[account]
-#[derive(InitSpace)]
-pub struct TreasuryConfig {
-    pub authority: Pubkey,
-    pub bump: u8,
-    pub is_paused: bool,
-    pub total_tvl: u64,
-    pub tvl_cap: u64,
-}
+    /// Hand the super admin seat to another key (super admin only)
+    pub fn transfer_super_admin(ctx: Context<SetRoles>, new_super_admin: Pubkey) -> Result<()> {
+        governance::transfer_super_admin(ctx, new_super_admin)
+    }
+
+    /// Replace the pause mask (pauser/guardian role)
+    ///
+    /// `withdrawal_pause_seconds` is only used when PAUSE_WITHDRAWALS is newly set
+    /// and must not exceed MAX_WITHDRAWAL_PAUSE_SECONDS.
+    pub fn set_pause_mask(
+        ctx: Context<RoleGatedAdmin>,
+        pause_mask: u8,
+        withdrawal_pause_seconds: i64,
+    ) -> Result<()> {
+        governance::set_pause_mask(ctx, pause_mask, withdrawal_pause_seconds)
+    }
+
+    /// Update the global TVL cap (risk manager role). Shard and pool quotas follow at
+    /// the next `aggregate_stats`, so crank it in the same transaction when lowering.
+    pub fn update_tvl_cap(ctx: Context<RoleGatedAdmin>, new_cap: u64) -> Result<()> {
+        governance::update_tvl_cap(ctx, new_cap)
+    }
+
+    /// Update the platform fee (risk manager role)
+    pub fn update_fee(ctx: Context<RoleGatedAdmin>, fee_basis_points: u16) -> Result<()> {
+        governance::update_fee(ctx, fee_basis_points)
+    }
+
+    /// Withdraw collected fees from the treasury (treasurer role). Once the admin
+    /// council holds treasury authority, withdrawals need `AdminAction::WithdrawTreasury`.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        governance::withdraw_treasury(ctx, amount)
+    }
+
+    /// Withdraw token fees collected in the treasury's token account for `mint`
+    /// (treasurer role); blocked once the admin council holds treasury authority,
+    /// like `withdraw_treasury`
+    pub fn withdraw_treasury_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawTreasuryTokens<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        governance::withdraw_treasury_tokens(ctx, amount)
+    }
+
+    /// Register (or replace) the guardians allowed to recover this vault
+    ///
+    /// Replacing the guardian set cancels any recovery in progress.
+    pub fn set_recovery_guardians(
+        ctx: Context<SetRecoveryGuardians>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        delay_seconds: i64,
+    ) -> Result<()> {
+        vaults::set_recovery_guardians(ctx, guardians, threshold, delay_seconds)
+    }
+
+    /// Start recovering a vault to `new_owner` (guardians only)
+    pub fn initiate_recovery(
+        ctx: Context<GuardianRecoveryAction>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        vaults::initiate_recovery(ctx, new_owner)
+    }
+
+    /// Approve the pending recovery (guardians only)
+    ///
+    /// `new_owner` must match the pending request so approvals can't be redirected.
+    pub fn approve_recovery(ctx: Context<GuardianRecoveryAction>, new_owner: Pubkey) -> Result<()> {
+        vaults::approve_recovery(ctx, new_owner)
+    }
+
+    /// Cancel a pending recovery (current owner only)
+    pub fn cancel_recovery(ctx: Context<CancelRecovery>) -> Result<()> {
+        vaults::cancel_recovery(ctx)
+    }
+
+    /// Migrate the vault and its config to the recovered owner once the delay has passed.
+    /// The old config stays behind marked `Recovered`; token vaults, withdrawal
+    /// tickets and allowances keyed by the old owner are handed over afterwards.
+    pub fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+        vaults::execute_recovery(ctx)
+    }
+
+    /// Move a recovered owner's token vault for `mint` to the new owner and close the
+    /// old one (new owner only). Hooked mints pass their extra accounts as remaining
+    /// accounts.
+    pub fn recover_token_vault<'info>(
+        ctx: Context<'_, '_, '_, 'info, RecoverTokenVault<'info>>,
+    ) -> Result<()> {
+        tokens::recover_token_vault(ctx)
+    }
+
+    /// Pay a recovered owner's queued pooled withdrawal to the new owner instead
+    /// (new owner only)
+    pub fn recover_withdrawal_ticket(ctx: Context<RecoverWithdrawalTicket>) -> Result<()> {
+        pool::recover_withdrawal_ticket(ctx)
+    }
+
+    /// Close an allowance granted on a recovered vault and reclaim its rent (new
+    /// owner only). Allowances don't carry over; grant them again on the new vault.
+    pub fn revoke_recovered_allowance(ctx: Context<RevokeRecoveredAllowance>) -> Result<()> {
+        vaults::revoke_recovered_allowance(ctx)
+    }
+
+    /// Name a beneficiary who can claim the vault after `inactivity_period` seconds
+    /// without any owner-signed instruction. Pass the default pubkey to clear it.
+    pub fn set_beneficiary(
+        ctx: Context<SetBeneficiary>,
+        beneficiary: Pubkey,
+        inactivity_period: i64,
+    ) -> Result<()> {
+        vaults::set_beneficiary(ctx, beneficiary, inactivity_period)
+    }
+
+    /// Claim the full vault balance as beneficiary of an inactive owner
+    pub fn claim_inheritance(ctx: Context<ClaimInheritance>) -> Result<()> {
+        vaults::claim_inheritance(ctx)
+    }
+
+    /// Let `spender` withdraw up to `amount_per_period` lamports every `period_seconds`
+    /// from the vault to a fixed `destination`. Re-granting replaces the terms.
+    pub fn grant_allowance(
+        ctx: Context<GrantAllowance>,
+        destination: Pubkey,
+        amount_per_period: u64,
+        period_seconds: i64,
+    ) -> Result<()> {
+        vaults::grant_allowance(ctx, destination, amount_per_period, period_seconds)
+    }
+
+    /// Revoke a spender's allowance and reclaim its rent (owner only)
+    pub fn revoke_allowance(ctx: Context<RevokeAllowance>) -> Result<()> {
+        vaults::revoke_allowance(ctx)
+    }
+
+    /// Withdraw from someone else's vault within a granted allowance (spender only)
+    pub fn withdraw_with_allowance(ctx: Context<WithdrawWithAllowance>, amount: u64) -> Result<()> {
+        vaults::withdraw_with_allowance(ctx, amount)
+    }
+
+    /// Create a shared vault for a fixed member list (creator must be a member). Vault
+    /// ids are scoped to the creator, so nobody else can take an id first.
+    pub fn create_joint_vault(
+        ctx: Context<CreateJointVault>,
+        vault_id: u64,
+        members: Vec<Pubkey>,
+        policy: JointWithdrawalPolicy,
+    ) -> Result<()> {
+        vaults::create_joint_vault(ctx, vault_id, members, policy)
+    }
+
+    /// Deposit SOL into a joint vault, credited to the signing member
+    pub fn joint_deposit(ctx: Context<JointDeposit>, amount: u64) -> Result<()> {
+        vaults::joint_deposit(ctx, amount)
+    }
+
+    /// Withdraw directly to the signing member under the AnyMember or Proportional policy
+    pub fn joint_withdraw(ctx: Context<JointWithdraw>, amount: u64) -> Result<()> {
+        vaults::joint_withdraw(ctx, amount)
+    }
+
+    /// Propose a withdrawal from a Threshold-policy joint vault (counts as one approval)
+    pub fn propose_joint_withdrawal(
+        ctx: Context<ProposeJointWithdrawal>,
+        amount: u64,
+        destination: Pubkey,
+    ) -> Result<()> {
+        vaults::propose_joint_withdrawal(ctx, amount, destination)
+    }
+
+    /// Approve a pending joint withdrawal (members only)
+    pub fn approve_joint_withdrawal(ctx: Context<ApproveJointWithdrawal>) -> Result<()> {
+        vaults::approve_joint_withdrawal(ctx)
+    }
+
+    /// Execute a joint withdrawal once enough members have approved
+    pub fn execute_joint_withdrawal(ctx: Context<ExecuteJointWithdrawal>) -> Result<()> {
+        vaults::execute_joint_withdrawal(ctx)
+    }
+
+    /// Create a rotating savings circle; `members` order is the payout order
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_circle(
+        ctx: Context<CreateCircle>,
+        circle_id: u64,
+        members: Vec<Pubkey>,
+        contribution: u64,
+        collateral: u64,
+        round_duration: i64,
+        late_penalty_basis_points: u16,
+    ) -> Result<()> {
+        vaults::create_circle(
+            ctx,
+            circle_id,
+            members,
+            contribution,
+            collateral,
+            round_duration,
+            late_penalty_basis_points,
+        )
+    }
+
+    /// Join a forming circle by posting collateral; the first round starts once everyone has joined
+    pub fn join_circle(ctx: Context<CircleMemberAction>) -> Result<()> {
+        vaults::join_circle(ctx)
+    }
+
+    /// Leave a circle that is still forming and take the posted collateral back
+    pub fn leave_circle(ctx: Context<CircleMemberAction>) -> Result<()> {
+        vaults::leave_circle(ctx)
+    }
+
+    /// Pay this round's contribution; paying after the round deadline adds a late penalty
+    pub fn contribute_to_circle(ctx: Context<CircleMemberAction>) -> Result<()> {
+        vaults::contribute_to_circle(ctx)
+    }
+
+    /// Crank: close the current round, forfeit collateral for missed payments and pay the pot
+    ///
+    /// Permissionless; callable once every member has paid or the round deadline has passed.
+    pub fn advance_circle_round(ctx: Context<AdvanceCircleRound>) -> Result<()> {
+        vaults::advance_circle_round(ctx)
+    }
+
+    /// Reclaim remaining collateral once the circle has completed
+    pub fn withdraw_circle_collateral(ctx: Context<CircleMemberAction>) -> Result<()> {
+        vaults::withdraw_circle_collateral(ctx)
+    }
+
+    /// Set the unit transfers of `mint` are rounded up to, in the mint's base units
+    /// (1 USDC is 1_000_000 at 6 decimals); 0 disables. Units are kept per mint
+    /// because mints differ in decimals.
+    pub fn set_round_up_unit(ctx: Context<SetRoundUpUnit>, round_up_unit: u64) -> Result<()> {
+        tokens::set_round_up_unit(ctx, round_up_unit)
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct UserConfig {
-    pub owner: Pubkey,
-    pub bump: u8,
-    pub vault_bump: u8,
+    /// Send an SPL token transfer and save the round-up to the next multiple of the
+    /// mint's round-up unit
+    ///
+    /// e.g. with a 1 USDC unit, sending 4.30 USDC saves 0.70 USDC (less the platform fee).
+    pub fn process_token_transfer<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProcessTokenTransfer<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        tokens::process_token_transfer(ctx, amount)
+    }
+
+    /// Move tokens owed by hooked transfers into the owner's token vault (permissionless).
+    ///
+    /// The hook only sees accounts read-only, so it records what is owed in
+    /// `PendingSavings` and this crank collects it: the owner approves their token
+    /// vault as delegate on the source account once, after which anyone can settle.
+    /// The hook is told first so it can check `amount` against what is pending; the
+    /// transfer itself then re-enters the hook with the vault as authority, which the
+    /// hook skips as already counted. Hooked mints pass the extra accounts for that
+    /// transfer as remaining accounts.
+    pub fn settle_hook_savings<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleHookSavings<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        tokens::settle_hook_savings(ctx, amount)
+    }
+
+    /// Withdraw saved SPL tokens from the user's token vault
+    pub fn withdraw_token_savings<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawTokenSavings<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        tokens::withdraw_token_savings(ctx, amount)
+    }
+
+    /// Set the percentage of each hooked Token-2022 transfer owed to savings (0 turns it off)
+    pub fn set_savings_rate(ctx: Context<SetSavingsRate>, savings_rate: u8) -> Result<()> {
+        tokens::set_savings_rate(ctx, savings_rate)
+    }
+
+    /// Create the user's token vault for a mint ahead of the first deposit
+    pub fn initialize_token_vault(ctx: Context<InitializeTokenVault>) -> Result<()> {
+        tokens::initialize_token_vault(ctx)
+    }
+
+    /// Set protocol-wide per-user limits, 0 meaning unlimited (risk manager role)
+    pub fn set_default_user_limits(
+        ctx: Context<RoleGatedAdmin>,
+        max_vault_balance: u64,
+        max_daily_deposit: u64,
+    ) -> Result<()> {
+        governance::set_default_user_limits(ctx, max_vault_balance, max_daily_deposit)
+    }
+
+    /// Override one user's limits; `None` falls back to the protocol default (risk manager role)
+    pub fn set_user_limits(
+        ctx: Context<ManageUser>,
+        max_vault_balance: Option<u64>,
+        max_daily_deposit: Option<u64>,
+    ) -> Result<()> {
+        governance::set_user_limits(ctx, max_vault_balance, max_daily_deposit)
+    }
+
+    /// Gate onboarding behind a verifier key and/or Merkle allowlist; both `None` opens it (risk manager role)
+    pub fn set_onboarding_gate(
+        ctx: Context<RoleGatedAdmin>,
+        verifier: Option<Pubkey>,
+        allowlist_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        governance::set_onboarding_gate(ctx, verifier, allowlist_root)
+    }
+
+    /// Point achievement NFT metadata at `{uri_base}{slug}.json` (risk manager role)
+    pub fn set_achievement_uri_base(ctx: Context<RoleGatedAdmin>, uri_base: String) -> Result<()> {
+        governance::set_achievement_uri_base(ctx, uri_base)
+    }
+
+    /// Freeze a vault: deposits stop and withdrawals can only go to `recovery_destination` (risk manager role)
+    pub fn freeze_user(ctx: Context<ManageUser>, recovery_destination: Pubkey) -> Result<()> {
+        governance::freeze_user(ctx, recovery_destination)
+    }
+
+    /// Lift a freeze (risk manager role)
+    pub fn unfreeze_user(ctx: Context<ManageUser>) -> Result<()> {
+        governance::unfreeze_user(ctx)
+    }
+
+    /// Withdraw from a frozen vault; funds always go to the recovery destination set at
+    /// freeze. Either the owner or a risk manager can push them there.
+    pub fn withdraw_frozen(ctx: Context<WithdrawFrozen>, amount: u64) -> Result<()> {
+        vaults::withdraw_frozen(ctx, amount)
+    }
+
+    /// Push tokens from a frozen user's token vault to the recovery destination's token
+    /// account for the mint (owner or risk manager)
+    pub fn withdraw_frozen_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawFrozenTokens<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        tokens::withdraw_frozen_tokens(ctx, amount)
+    }
+
+    /// Create one TVL stats shard; anyone may pay for it
+    pub fn initialize_stats_shard(ctx: Context<InitializeStatsShard>, index: u8) -> Result<()> {
+        governance::initialize_stats_shard(ctx, index)
+    }
+
+    /// Permissionless crank: total TVL across every stats shard (passed writable, in
+    /// index order, as remaining accounts), the share pool and launch-era balances not
+    /// yet migrated, then split whatever is left under the cap evenly between the
+    /// shards and the pool as fresh quota. Deposits only check their own slot's quota,
+    /// so the slots together can never take TVL past the cap. When the reward pool is
+    /// passed, every shard also settles its rewards with it.
+    ///
+    /// Compute budget: 50,000 CU (enforced by tests/compute-units.ts)
+    pub fn aggregate_stats<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AggregateStats<'info>>,
+    ) -> Result<()> {
+        governance::aggregate_stats(ctx)
+    }
+
+    /// Fund many vaults from one payer (e.g. payroll). Remaining accounts are every
+    /// stats shard in index order, then one (user_config, vault) pair per amount.
+    /// Fees go to the treasury in one transfer.
+    ///
+    /// Compute budget: 40,000 CU for two vaults (enforced by tests/compute-units.ts)
+    pub fn batch_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchDeposit<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        vaults::batch_deposit(ctx, amounts)
+    }
+
+    /// Fund someone else's vault (gifts, employer matching). The beneficiary doesn't
+    /// sign; the payer covers the platform fee on top of `amount`.
+    ///
+    /// Compute budget: 20,000 CU (enforced by tests/compute-units.ts)
+    pub fn deposit_for(
+        ctx: Context<DepositFor>,
+        beneficiary: Pubkey,
+        amount: u64,
+        memo: Option<String>,
+    ) -> Result<()> {
+        vaults::deposit_for(ctx, beneficiary, amount, memo)
+    }
+
+    /// Sponsor a matching program that tops up user deposits at `match_basis_points`,
+    /// up to `per_user_cap` lamports per user every `period_seconds`
+    pub fn create_matching_program(
+        ctx: Context<CreateMatchingProgram>,
+        program_id: u64,
+        match_basis_points: u16,
+        per_user_cap: u64,
+        period_seconds: i64,
+    ) -> Result<()> {
+        vaults::create_matching_program(
+            ctx,
+            program_id,
+            match_basis_points,
+            per_user_cap,
+            period_seconds,
+        )
+    }
+
+    /// Sponsor adds budget to a matching program
+    pub fn fund_matching_program(ctx: Context<ManageMatchingProgram>, amount: u64) -> Result<()> {
+        vaults::fund_matching_program(ctx, amount)
+    }
+
+    /// Sponsor takes back unspent budget
+    pub fn withdraw_matching_budget(
+        ctx: Context<ManageMatchingProgram>,
+        amount: u64,
+    ) -> Result<()> {
+        vaults::withdraw_matching_budget(ctx, amount)
+    }
+
+    /// Enroll in a matching program so `deposit` can pay out matches. The sponsor
+    /// co-signs, so only the users they approve can draw on their budget.
+    pub fn join_matching_program(ctx: Context<JoinMatchingProgram>) -> Result<()> {
+        vaults::join_matching_program(ctx)
+    }
+
+    /// Turn reward points into fee credit, spent on the next deposit/withdraw fees
+    pub fn redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+        vaults::redeem_points(ctx, points)
+    }
+
+    /// Set the savings target used by the goal-reached achievement (0 = no goal)
+    pub fn set_savings_goal(ctx: Context<SetSavingsGoal>, savings_goal: u64) -> Result<()> {
+        vaults::set_savings_goal(ctx, savings_goal)
+    }
+
+    /// Mint a soulbound (non-transferable) Token-2022 NFT for a milestone the user has
+    /// reached. Each milestone can be claimed once per user.
+    pub fn claim_achievement(ctx: Context<ClaimAchievement>, milestone: Milestone) -> Result<()> {
+        tokens::claim_achievement(ctx, milestone)
+    }
+
+    /// Create the reward pool that returns fee income to savers (treasurer role)
+    pub fn initialize_reward_pool(ctx: Context<InitializeRewardPool>) -> Result<()> {
+        pool::initialize_reward_pool(ctx)
+    }
+
+    /// Start a reward epoch: move `amount` from the treasury and stream it to savers
+    /// pro rata to their balances over `duration_seconds`. Rewards left from a running
+    /// epoch roll into the new one (treasurer role).
+    pub fn distribute_rewards(
+        ctx: Context<DistributeRewards>,
+        amount: u64,
+        duration_seconds: i64,
+    ) -> Result<()> {
+        pool::distribute_rewards(ctx, amount, duration_seconds)
+    }
+
+    /// Permissionless: settle the owner's stats shard with the reward pool and bring
+    /// the owner's accrued rewards up to date. Also registers vaults that held a
+    /// balance before rewards launched.
+    ///
+    /// Compute budget: 10,000 CU (enforced by tests/compute-units.ts)
+    pub fn sync_rewards(ctx: Context<SyncRewards>) -> Result<()> {
+        pool::sync_rewards(ctx)
+    }
+
+    /// Pay out the caller's accrued rewards into their wallet
+    ///
+    /// Compute budget: 20,000 CU (enforced by tests/compute-units.ts)
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        pool::claim_rewards(ctx)
+    }
+
+    /// Create the pooled share vault and its share mint (treasurer role). The admin
+    /// seeds the vault with its rent-exempt minimum, which never backs any shares.
+    /// Deposits open once `aggregate_stats` has given the pool a quota.
+    pub fn initialize_share_pool(ctx: Context<InitializeSharePool>) -> Result<()> {
+        pool::initialize_share_pool(ctx)
+    }
+
+    /// Deposit SOL into the pooled vault and mint savings shares at the current share price
+    pub fn deposit_shares(ctx: Context<DepositShares>, amount: u64) -> Result<()> {
+        pool::deposit_shares(ctx, amount)
+    }
+
+    /// Burn savings shares for their current SOL value. Anyone holding shares can
+    /// redeem them; no savings account is needed, but a holder whose account is
+    /// frozen or recovered can't.
+    pub fn redeem_shares(ctx: Context<RedeemShares>, shares: u64) -> Result<()> {
+        pool::redeem_shares(ctx, shares)
+    }
+
+    /// Move `amount` of treasury income into the pooled vault, raising the share
+    /// price for every holder (treasurer role)
+    pub fn accrue_share_yield(ctx: Context<AccrueShareYield>, amount: u64) -> Result<()> {
+        pool::accrue_share_yield(ctx, amount)
+    }
+
+    /// Deposit SOL in pooled mode: the funds join the share pool and the shares are
+    /// recorded in `UserConfig` rather than minted
+    ///
+    /// Compute budget: 20,000 CU (enforced by tests/compute-units.ts)
+    pub fn deposit_pooled(ctx: Context<PooledVault>, amount: u64) -> Result<()> {
+        pool::deposit_pooled(ctx, amount)
+    }
+
+    /// Withdraw the current SOL value of `shares` from the caller's pooled position
+    ///
+    /// Compute budget: 20,000 CU (enforced by tests/compute-units.ts)
+    pub fn withdraw_pooled(ctx: Context<PooledVault>, shares: u64) -> Result<()> {
+        pool::withdraw_pooled(ctx, shares)
+    }
+
+    /// Move `amount` lamports from the personal vault into the caller's pooled
+    /// position. Internal moves pay no platform fee.
+    pub fn migrate_to_pool(ctx: Context<MigrateVault>, amount: u64) -> Result<()> {
+        pool::migrate_to_pool(ctx, amount)
+    }
+
+    /// Move the SOL value of `shares` from the caller's pooled position back into
+    /// their personal vault. Allowed while frozen, so the funds become reachable by
+    /// `withdraw_frozen`.
+    pub fn migrate_to_personal(ctx: Context<MigrateVault>, shares: u64) -> Result<()> {
+        pool::migrate_to_personal(ctx, shares)
+    }
+
+    /// Create the FIFO queue for pooled withdrawals that exceed the pool's liquid
+    /// balance (risk manager role)
+    pub fn initialize_withdrawal_queue(
+        ctx: Context<InitializeWithdrawalQueue>,
+        notice_seconds: i64,
+    ) -> Result<()> {
+        pool::initialize_withdrawal_queue(ctx, notice_seconds)
+    }
+
+    /// Change the notice period for tickets requested from now on (risk manager role)
+    pub fn set_withdrawal_notice(
+        ctx: Context<SetWithdrawalNotice>,
+        notice_seconds: i64,
+    ) -> Result<()> {
+        pool::set_withdrawal_notice(ctx, notice_seconds)
+    }
+
+    /// Queue a withdrawal of `shares` from the caller's pooled position. The shares
+    /// are priced now and stop earning; the lamports are reserved for the ticket and
+    /// can be claimed after the notice period once every earlier ticket is paid.
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+        pool::request_withdrawal(ctx, shares)
+    }
+
+    /// Queue minted savings shares for withdrawal: they are burned now and their SOL
+    /// value is paid by `claim_withdrawal` like a pooled ticket
+    pub fn request_share_withdrawal(
+        ctx: Context<RequestShareWithdrawal>,
+        shares: u64,
+    ) -> Result<()> {
+        pool::request_share_withdrawal(ctx, shares)
+    }
+
+    /// Pay out the ticket at the front of the queue once its notice period has
+    /// passed and the share vault holds enough lamports. Anyone can crank this; the
+    /// payout and the ticket's rent go to the ticket owner. A frozen owner's ticket
+    /// won't pay: it has to be cancelled back into their position first. Cancelled
+    /// tickets pay nothing and just move the queue along.
+    pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>) -> Result<()> {
+        pool::claim_withdrawal(ctx)
+    }
+
+    /// Cancel a queued withdrawal, buying shares back at today's price for the
+    /// ticket's amount: into the pooled position or as share tokens, matching where
+    /// they came from. The ticket stays in line, empty, until `claim_withdrawal`
+    /// passes over it. Ticket owner, or a risk manager to unblock the queue behind a
+    /// frozen owner.
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        pool::cancel_withdrawal(ctx)
+    }
+
+    /// Set a negotiated fee rate, zero included, for a user or an integrating partner
+    /// program. Replaces the protocol fee on every deposit and withdrawal path that
+    /// takes the override account (treasurer role).
+    pub fn set_fee_override(
+        ctx: Context<SetFeeOverride>,
+        subject: Pubkey,
+        fee_basis_points: u16,
+    ) -> Result<()> {
+        governance::set_fee_override(ctx, subject, fee_basis_points)
+    }
+
+    /// Drop a fee override so the protocol rate applies again (treasurer role)
+    pub fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+        governance::remove_fee_override(ctx)
+    }
 }
 
 // Error Codes
 #[error_code]
 pub enum ErrorCode {
-    #[msg("Invalid savings rate. Must be between 1-90%")]
+    #[msg("Invalid savings rate. Must be between 0-90%")]
     InvalidSavingsRate,
     #[msg("Invalid amount")]
     InvalidAmount,
@@ -331,4 +844,112 @@ pub enum ErrorCode {
     ProtocolPaused,
     #[msg("TVL cap exceeded")]
     TvlCapExceeded,
+    #[msg("Invalid admin council configuration")]
+    InvalidCouncil,
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Signer already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Approval threshold not met")]
+    ThresholdNotMet,
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Destination does not match proposal")]
+    InvalidDestination,
+    #[msg("Fee exceeds maximum allowed")]
+    FeeTooHigh,
+    #[msg("Signer lacks the required admin role")]
+    MissingRole,
+    #[msg("Invalid role assignment")]
+    InvalidRoles,
+    #[msg("Roles account required for this action")]
+    MissingRolesAccount,
+    #[msg("Unknown pause flag")]
+    InvalidPauseFlags,
+    #[msg("Withdrawal pause must be between 1 second and the maximum duration")]
+    WithdrawalPauseTooLong,
+    #[msg("Withdrawals were paused too recently to pause again")]
+    WithdrawalPauseCooldown,
+    #[msg("Invalid recovery guardian configuration")]
+    InvalidGuardians,
+    #[msg("A recovery is already in progress")]
+    RecoveryInProgress,
+    #[msg("No recovery is pending")]
+    NoRecoveryPending,
+    #[msg("Invalid recovery owner")]
+    InvalidRecoveryOwner,
+    #[msg("Invalid beneficiary configuration")]
+    InvalidBeneficiary,
+    #[msg("Owner has been active within the inactivity period")]
+    OwnerStillActive,
+    #[msg("Invalid allowance configuration")]
+    InvalidAllowance,
+    #[msg("Withdrawal exceeds the remaining allowance for this period")]
+    AllowanceExceeded,
+    #[msg("Invalid joint vault configuration")]
+    InvalidJointVault,
+    #[msg("Signer is not a member of this joint vault")]
+    NotJointMember,
+    #[msg("Withdrawal exceeds member's contribution")]
+    ExceedsContribution,
+    #[msg("This joint vault requires approved withdrawal proposals")]
+    ApprovalRequired,
+    #[msg("Invalid savings circle configuration")]
+    InvalidCircle,
+    #[msg("Savings circle is not in the required state")]
+    InvalidCircleState,
+    #[msg("Signer is not a member of this savings circle")]
+    NotCircleMember,
+    #[msg("Member already joined this circle")]
+    AlreadyJoined,
+    #[msg("Member already contributed this round")]
+    AlreadyContributed,
+    #[msg("Round is still open")]
+    RoundNotFinished,
+    #[msg("Per-user deposit limit exceeded")]
+    UserLimitExceeded,
+    #[msg("User is not on the onboarding allowlist")]
+    NotAllowlisted,
+    #[msg("Account is frozen")]
+    AccountFrozen,
+    #[msg("Account is not frozen")]
+    AccountNotFrozen,
+    #[msg("Vault was recovered to a new owner")]
+    AccountRecovered,
+    #[msg("Invalid stats shard")]
+    InvalidStatsShard,
+    #[msg("Batch accounts do not match the amounts")]
+    InvalidBatch,
+    #[msg("Memo is too long")]
+    MemoTooLong,
+    #[msg("Invalid matching program")]
+    InvalidMatchingProgram,
+    #[msg("Not enough reward points")]
+    InsufficientPoints,
+    #[msg("Milestone not reached")]
+    MilestoneNotReached,
+    #[msg("Not enough liquid funds in the pool; request a queued withdrawal")]
+    InsufficientLiquidity,
+    #[msg("Withdrawal ticket is not at the front of the queue or still in its notice period")]
+    WithdrawalNotReady,
+    #[msg("Withdrawal notice period out of range")]
+    InvalidWithdrawalNotice,
+    #[msg("Fee override does not apply to this user or caller")]
+    InvalidFeeOverride,
+    #[msg("Account is already on the current layout version")]
+    AlreadyMigrated,
+    #[msg("Treasury is governed by the admin council; submit a proposal")]
+    CouncilGoverned,
+    #[msg("Savings goal is below the minimum")]
+    InvalidSavingsGoal,
+    #[msg("Achievement metadata URI base is not set")]
+    AchievementsNotConfigured,
+    #[msg("Achievement metadata URI base is too long")]
+    AchievementUriTooLong,
+    #[msg("Share pool accounts are required to settle a pooled position")]
+    SharePoolAccountsRequired,
+    #[msg("Withdrawal ticket has been cancelled")]
+    WithdrawalCancelled,
+    #[msg("The ticket owner's savings or share token account is required")]
+    TicketOwnerAccountRequired,
 }
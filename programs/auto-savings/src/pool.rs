@@ -0,0 +1,1774 @@
+//! The share pool, pooled vault positions, the withdrawal queue and staking rewards
+
+use crate::*;
+
+pub(crate) fn recover_withdrawal_ticket(ctx: Context<RecoverWithdrawalTicket>) -> Result<()> {
+    let new_owner = ctx.accounts.new_owner.key();
+    ctx.accounts
+        .old_user_config
+        .require_recovered_to(&new_owner)?;
+
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.owner = new_owner;
+
+    log_msg!("Withdrawal ticket {} recovered to {}", ticket.id, new_owner);
+    Ok(())
+}
+
+pub(crate) fn initialize_reward_pool(ctx: Context<InitializeRewardPool>) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+    let reward_pool = &mut ctx.accounts.reward_pool;
+    reward_pool.acc_reward_per_share = 0;
+    reward_pool.total_staked = 0;
+    reward_pool.reward_rate = 0;
+    reward_pool.last_update_ts = Clock::get()?.unix_timestamp;
+    reward_pool.epoch_end_ts = 0;
+    reward_pool.epoch = 0;
+    reward_pool.bump = ctx.bumps.reward_pool;
+    reward_pool.funds_bump = ctx.bumps.reward_funds;
+
+    log_msg!("Reward pool initialized");
+    Ok(())
+}
+
+pub(crate) fn distribute_rewards(
+    ctx: Context<DistributeRewards>,
+    amount: u64,
+    duration_seconds: i64,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+    require!(duration_seconds > 0, ErrorCode::InvalidAmount);
+
+    withdraw_from_treasury(
+        &ctx.accounts.treasury,
+        &ctx.accounts.reward_funds,
+        &ctx.accounts.system_program,
+        ctx.accounts.treasury_config.treasury_bump,
+        amount,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let reward_pool = &mut ctx.accounts.reward_pool;
+    reward_pool.update(now)?;
+    let leftover = if now < reward_pool.epoch_end_ts {
+        ((reward_pool.epoch_end_ts - now) as u64)
+            .checked_mul(reward_pool.reward_rate)
+            .ok_or(ErrorCode::Overflow)?
+    } else {
+        0
+    };
+    let epoch_rewards = amount.checked_add(leftover).ok_or(ErrorCode::Overflow)?;
+    reward_pool.reward_rate = epoch_rewards / duration_seconds as u64;
+    require!(reward_pool.reward_rate > 0, ErrorCode::InvalidAmount);
+    reward_pool.epoch_end_ts = now
+        .checked_add(duration_seconds)
+        .ok_or(ErrorCode::Overflow)?;
+    reward_pool.epoch = reward_pool
+        .epoch
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    log_msg!(
+        "Reward epoch {}: {} lamports over {}s",
+        reward_pool.epoch,
+        epoch_rewards,
+        duration_seconds
+    );
+    Ok(())
+}
+
+pub(crate) fn sync_rewards(ctx: Context<SyncRewards>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.settle_rewards(&mut ctx.accounts.reward_pool, now)?;
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.sync_rewards(&mut stats_shard)?;
+
+    log_msg!(
+        "Synced rewards: {} lamports pending",
+        user_config.pending_rewards
+    );
+    Ok(())
+}
+
+pub(crate) fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+    ctx.accounts.user_config.require_active()?;
+    let now = Clock::get()?.unix_timestamp;
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.settle_rewards(&mut ctx.accounts.reward_pool, now)?;
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+    user_config.record_activity(now);
+
+    // Nothing accrued yet (or the funds are empty) is not an error
+    let available = ctx
+        .accounts
+        .reward_funds
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    let payout = user_config.pending_rewards.min(available);
+    if payout == 0 {
+        log_msg!("No rewards to claim");
+        return Ok(());
+    }
+    user_config.pending_rewards -= payout;
+
+    transfer_from_pda(
+        &ctx.accounts.reward_funds,
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.system_program,
+        &[
+            b"reward_funds".as_ref(),
+            &[ctx.accounts.reward_pool.funds_bump],
+        ],
+        payout,
+    )?;
+
+    log_msg!("Claimed {} lamports of rewards", payout);
+    Ok(())
+}
+
+pub(crate) fn initialize_share_pool(ctx: Context<InitializeSharePool>) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+
+    let rent_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.admin.to_account_info(),
+            to: ctx.accounts.share_vault.to_account_info(),
+        },
+    );
+    transfer(rent_transfer, Rent::get()?.minimum_balance(0))?;
+
+    let share_pool = &mut ctx.accounts.share_pool;
+    share_pool.total_assets = 0;
+    share_pool.pooled_shares = 0;
+    share_pool.queued = 0;
+    share_pool.tvl_quota = 0;
+    share_pool.bump = ctx.bumps.share_pool;
+    share_pool.mint_bump = ctx.bumps.share_mint;
+    share_pool.vault_bump = ctx.bumps.share_vault;
+
+    log_msg!(
+        "Share pool initialized with mint {}",
+        ctx.accounts.share_mint.key()
+    );
+    Ok(())
+}
+
+pub(crate) fn deposit_shares(ctx: Context<DepositShares>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let user_config = &mut ctx.accounts.user_config;
+    let platform_fee = user_config.apply_fee_credit(calculate_fee(amount, fee_basis_points)?);
+    let amount_after_fee = amount
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    // The user's share position counts toward their per-user vault balance cap
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let position = share_pool.shares_to_lamports(ctx.accounts.user_shares.amount, supply)?;
+    user_config.record_deposit(treasury_config, position, amount, amount_after_fee, now)?;
+    user_config.record_streak(amount_after_fee, now)?;
+    // Not counted in `net_deposited`: minted shares can be transferred away
+
+    let shares = share_pool.issue(amount_after_fee, supply)?;
+    share_pool.require_within_quota()?;
+
+    if platform_fee > 0 {
+        let fee_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        transfer(fee_transfer, platform_fee)?;
+    }
+
+    let vault_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: ctx.accounts.share_vault.to_account_info(),
+        },
+    );
+    transfer(vault_transfer, amount_after_fee)?;
+
+    let seeds: &[&[u8]] = &[b"share_pool".as_ref(), &[share_pool.bump]];
+    let signer_seeds = &[seeds];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.user_shares.to_account_info(),
+                authority: share_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        shares,
+    )?;
+
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Deposited {} lamports for {} shares (fee: {} lamports)",
+        amount_after_fee,
+        shares,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn redeem_shares(ctx: Context<RedeemShares>, shares: u64) -> Result<()> {
+    require!(shares > 0, ErrorCode::InvalidAmount);
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    record_holder_activity(&ctx.accounts.user_config, now)?;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let lamports = share_pool.retire(shares, supply)?;
+    share_pool.require_liquid(ctx.accounts.share_vault.lamports(), lamports)?;
+    let platform_fee = calculate_fee(lamports, fee_basis_points)?;
+    let payout = lamports
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.user_shares.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let seeds: &[&[u8]] = &[b"share_vault".as_ref(), &[share_pool.vault_bump]];
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        payout,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    log_msg!(
+        "Redeemed {} shares for {} lamports (fee: {} lamports)",
+        shares,
+        payout,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn accrue_share_yield(ctx: Context<AccrueShareYield>, amount: u64) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_TREASURER)?;
+    // With no shares outstanding the yield would belong to the next depositor
+    require!(
+        ctx.accounts
+            .share_pool
+            .total_shares(ctx.accounts.share_mint.supply)?
+            > 0,
+        ErrorCode::InsufficientFunds
+    );
+
+    withdraw_from_treasury(
+        &ctx.accounts.treasury,
+        &ctx.accounts.share_vault,
+        &ctx.accounts.system_program,
+        ctx.accounts.treasury_config.treasury_bump,
+        amount,
+    )?;
+
+    // Yield can take TVL past the cap; `aggregate_stats` then hands out no new
+    // quota until withdrawals bring it back under
+    let share_pool = &mut ctx.accounts.share_pool;
+    share_pool.total_assets = share_pool
+        .total_assets
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    log_msg!(
+        "Accrued {} lamports of share yield ({} lamports backing shares)",
+        amount,
+        share_pool.total_assets
+    );
+    Ok(())
+}
+
+pub(crate) fn deposit_pooled(ctx: Context<PooledVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let user_config = &mut ctx.accounts.user_config;
+    let platform_fee = user_config.apply_fee_credit(calculate_fee(amount, fee_basis_points)?);
+    let amount_after_fee = amount
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let position = share_pool.shares_to_lamports(user_config.pooled_shares, supply)?;
+    user_config.record_deposit(treasury_config, position, amount, amount_after_fee, now)?;
+    user_config.record_streak(amount_after_fee, now)?;
+    user_config.record_saved(amount_after_fee)?;
+
+    let shares = share_pool.issue(amount_after_fee, supply)?;
+    share_pool.require_within_quota()?;
+    share_pool.pooled_shares = share_pool
+        .pooled_shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+    user_config.pooled_shares = user_config
+        .pooled_shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+
+    if platform_fee > 0 {
+        let fee_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        transfer(fee_transfer, platform_fee)?;
+    }
+
+    let vault_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: ctx.accounts.share_vault.to_account_info(),
+        },
+    );
+    transfer(vault_transfer, amount_after_fee)?;
+
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Deposited {} lamports to the pool for {} shares (fee: {} lamports)",
+        amount_after_fee,
+        shares,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn withdraw_pooled(ctx: Context<PooledVault>, shares: u64) -> Result<()> {
+    require!(shares > 0, ErrorCode::InvalidAmount);
+
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.require_active()?;
+    require!(
+        user_config.pooled_shares >= shares,
+        ErrorCode::InsufficientFunds
+    );
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let lamports = share_pool.retire(shares, supply)?;
+    share_pool.require_liquid(ctx.accounts.share_vault.lamports(), lamports)?;
+    share_pool.pooled_shares -= shares;
+    user_config.pooled_shares -= shares;
+    user_config.record_withdrawn(lamports);
+
+    let platform_fee = user_config.apply_fee_credit(calculate_fee(lamports, fee_basis_points)?);
+    let payout = lamports
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let seeds: &[&[u8]] = &[b"share_vault".as_ref(), &[share_pool.vault_bump]];
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        payout,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Withdrawn {} pooled shares for {} lamports (fee: {} lamports)",
+        shares,
+        payout,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn migrate_to_pool(ctx: Context<MigrateVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    // Funds enter the pool, so a deposit pause stops this as well
+    require!(
+        !treasury_config.is_paused(PAUSE_SWAPS, now)
+            && !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let user_config = &mut ctx.accounts.user_config;
+    require!(
+        ctx.accounts.vault.lamports() >= amount,
+        ErrorCode::InsufficientFunds
+    );
+
+    // The pooled position is held to the same per-user limits as a deposit
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let position = share_pool.shares_to_lamports(user_config.pooled_shares, supply)?;
+    user_config.record_deposit(treasury_config, position, amount, amount, now)?;
+    let shares = share_pool.issue(amount, supply)?;
+    share_pool.pooled_shares = share_pool
+        .pooled_shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+    user_config.pooled_shares = user_config
+        .pooled_shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.share_vault.to_account_info(),
+        &ctx.accounts.system_program,
+        &[
+            b"vault".as_ref(),
+            ctx.accounts.user.key().as_ref(),
+            &[user_config.vault_bump],
+        ],
+        amount,
+    )?;
+
+    // TVL only changes slot, so the quota moves with it instead of being checked
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.sub_tvl(amount)?;
+    share_pool.add_quota(stats_shard.release_quota(amount))?;
+    user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Migrated {} lamports to the pool for {} shares",
+        amount,
+        shares
+    );
+    Ok(())
+}
+
+pub(crate) fn migrate_to_personal(ctx: Context<MigrateVault>, shares: u64) -> Result<()> {
+    require!(shares > 0, ErrorCode::InvalidAmount);
+    require!(
+        !ctx.accounts
+            .treasury_config
+            .is_paused(PAUSE_SWAPS, Clock::get()?.unix_timestamp),
+        ErrorCode::ProtocolPaused
+    );
+
+    let user_config = &mut ctx.accounts.user_config;
+    require!(
+        user_config.pooled_shares >= shares,
+        ErrorCode::InsufficientFunds
+    );
+
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let lamports = share_pool.retire(shares, supply)?;
+    share_pool.require_liquid(ctx.accounts.share_vault.lamports(), lamports)?;
+    share_pool.pooled_shares -= shares;
+    user_config.pooled_shares -= shares;
+
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.system_program,
+        &[b"share_vault".as_ref(), &[share_pool.vault_bump]],
+        lamports,
+    )?;
+
+    // TVL only changes slot, so the quota moves with it instead of being checked
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.add_tvl(lamports)?;
+    stats_shard.add_quota(share_pool.release_quota(lamports))?;
+    user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    let now = Clock::get()?.unix_timestamp;
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Migrated {} pooled shares to the personal vault ({} lamports)",
+        shares,
+        lamports
+    );
+    Ok(())
+}
+
+pub(crate) fn initialize_withdrawal_queue(
+    ctx: Context<InitializeWithdrawalQueue>,
+    notice_seconds: i64,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    require!(
+        (0..=MAX_WITHDRAWAL_NOTICE_SECONDS).contains(&notice_seconds),
+        ErrorCode::InvalidWithdrawalNotice
+    );
+
+    let withdrawal_queue = &mut ctx.accounts.withdrawal_queue;
+    withdrawal_queue.head = 0;
+    withdrawal_queue.tail = 0;
+    withdrawal_queue.notice_seconds = notice_seconds;
+    withdrawal_queue.last_unlock_ts = 0;
+    withdrawal_queue.bump = ctx.bumps.withdrawal_queue;
+
+    log_msg!("Withdrawal queue initialized ({}s notice)", notice_seconds);
+    Ok(())
+}
+
+pub(crate) fn set_withdrawal_notice(
+    ctx: Context<SetWithdrawalNotice>,
+    notice_seconds: i64,
+) -> Result<()> {
+    ctx.accounts
+        .roles
+        .require_role(&ctx.accounts.admin.key(), ROLE_RISK_MANAGER)?;
+    require!(
+        (0..=MAX_WITHDRAWAL_NOTICE_SECONDS).contains(&notice_seconds),
+        ErrorCode::InvalidWithdrawalNotice
+    );
+
+    ctx.accounts.withdrawal_queue.notice_seconds = notice_seconds;
+
+    log_msg!("Withdrawal notice set to {}s", notice_seconds);
+    Ok(())
+}
+
+pub(crate) fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+    require!(shares > 0, ErrorCode::InvalidAmount);
+
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.require_active()?;
+    require!(
+        user_config.pooled_shares >= shares,
+        ErrorCode::InsufficientFunds
+    );
+
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let amount = share_pool.retire(shares, supply)?;
+    share_pool.pooled_shares -= shares;
+    user_config.pooled_shares -= shares;
+    user_config.record_withdrawn(amount);
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+    share_pool.queued = share_pool
+        .queued
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let ticket = &mut ctx.accounts.ticket;
+    ctx.accounts.withdrawal_queue.enqueue(
+        ticket,
+        ctx.accounts.user.key(),
+        amount,
+        true,
+        ctx.bumps.ticket,
+        now,
+    )?;
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Withdrawal ticket {} queued for {} lamports",
+        ticket.id,
+        amount
+    );
+    Ok(())
+}
+
+pub(crate) fn request_share_withdrawal(
+    ctx: Context<RequestShareWithdrawal>,
+    shares: u64,
+) -> Result<()> {
+    require!(shares > 0, ErrorCode::InvalidAmount);
+    let now = Clock::get()?.unix_timestamp;
+    record_holder_activity(&ctx.accounts.user_config, now)?;
+
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let amount = share_pool.retire(shares, supply)?;
+    share_pool.queued = share_pool
+        .queued
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.user_shares.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let ticket = &mut ctx.accounts.ticket;
+    ctx.accounts.withdrawal_queue.enqueue(
+        ticket,
+        ctx.accounts.user.key(),
+        amount,
+        false,
+        ctx.bumps.ticket,
+        now,
+    )?;
+
+    log_msg!(
+        "Withdrawal ticket {} queued for {} lamports",
+        ticket.id,
+        amount
+    );
+    Ok(())
+}
+
+pub(crate) fn claim_withdrawal(ctx: Context<ClaimWithdrawal>) -> Result<()> {
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let ticket = &ctx.accounts.ticket;
+    let withdrawal_queue = &mut ctx.accounts.withdrawal_queue;
+    let cancelled = ticket.amount == 0;
+    require!(
+        ticket.id == withdrawal_queue.head && (cancelled || now >= ticket.unlock_ts),
+        ErrorCode::WithdrawalNotReady
+    );
+    if !cancelled {
+        require_holder_active(&ctx.accounts.owner_config)?;
+    }
+
+    // The front ticket has first call on every reserved lamport
+    let amount = ticket.amount;
+    let available = ctx
+        .accounts
+        .share_vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    require!(available >= amount, ErrorCode::InsufficientLiquidity);
+
+    let fee_basis_points =
+        fee_basis_points_for(treasury_config, &ctx.accounts.fee_rate, &ticket.owner)?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let payout = amount
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let vault_bump = ctx.accounts.share_pool.vault_bump;
+    let seeds: &[&[u8]] = &[b"share_vault".as_ref(), &[vault_bump]];
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        payout,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.share_vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    let share_pool = &mut ctx.accounts.share_pool;
+    share_pool.queued = share_pool
+        .queued
+        .checked_sub(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    withdrawal_queue.head = withdrawal_queue
+        .head
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit!(WithdrawalClaimed {
+        owner: ticket.owner,
+        ticket_id: ticket.id,
+        amount: payout,
+        fee: platform_fee,
+    });
+    log_msg!(
+        "Withdrawal ticket {} paid {} lamports (fee: {} lamports)",
+        ticket.id,
+        payout,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    let ticket = &mut ctx.accounts.ticket;
+    require!(
+        authority == ticket.owner || ctx.accounts.roles.has_role(&authority, ROLE_RISK_MANAGER),
+        ErrorCode::Unauthorized
+    );
+    require!(ticket.amount > 0, ErrorCode::WithdrawalCancelled);
+
+    let amount = ticket.amount;
+    let share_pool = &mut ctx.accounts.share_pool;
+    let supply = share_pool.total_shares(ctx.accounts.share_mint.supply)?;
+    let shares = share_pool.issue(amount, supply)?;
+    share_pool.queued = share_pool
+        .queued
+        .checked_sub(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    ticket.amount = 0;
+
+    if ticket.pooled {
+        let owner_config = ctx
+            .accounts
+            .owner_config
+            .as_mut()
+            .ok_or(ErrorCode::TicketOwnerAccountRequired)?;
+        share_pool.pooled_shares = share_pool
+            .pooled_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::Overflow)?;
+        owner_config.pooled_shares = owner_config
+            .pooled_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::Overflow)?;
+        owner_config.record_saved(amount)?;
+        if authority == owner_config.owner {
+            owner_config.record_activity(Clock::get()?.unix_timestamp);
+        }
+    } else {
+        let owner_shares = ctx
+            .accounts
+            .owner_shares
+            .as_ref()
+            .ok_or(ErrorCode::TicketOwnerAccountRequired)?;
+        // A frozen owner's shares go back into their account frozen
+        let frozen = owner_shares.is_frozen();
+        if frozen {
+            set_shares_frozen(
+                share_pool,
+                &ctx.accounts.share_mint,
+                owner_shares,
+                &ctx.accounts.token_program,
+                false,
+            )?;
+        }
+        let seeds: &[&[u8]] = &[b"share_pool".as_ref(), &[share_pool.bump]];
+        let signer_seeds = &[seeds];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: owner_shares.to_account_info(),
+                    authority: share_pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            shares,
+        )?;
+        if frozen {
+            set_shares_frozen(
+                share_pool,
+                &ctx.accounts.share_mint,
+                owner_shares,
+                &ctx.accounts.token_program,
+                true,
+            )?;
+        }
+    }
+
+    emit!(WithdrawalCancelled {
+        owner: ticket.owner,
+        ticket_id: ticket.id,
+        amount,
+        shares,
+        cancelled_by: authority,
+    });
+    log_msg!(
+        "Withdrawal ticket {} cancelled for {} shares",
+        ticket.id,
+        shares
+    );
+    Ok(())
+}
+
+/// Share holders need no savings account, but one that has been frozen or recovered
+/// can't take value out of the pool
+pub(crate) fn require_holder_active(holder_config: &AccountInfo) -> Result<()> {
+    if holder_config.owner == &crate::ID && !holder_config.data_is_empty() {
+        UserConfig::try_deserialize(&mut &holder_config.data.borrow()[..])?.require_active()?;
+    }
+    Ok(())
+}
+
+/// Same check for a holder who signed, whose activity also resets their
+/// inheritance timer
+pub(crate) fn record_holder_activity(holder_config: &AccountInfo, now: i64) -> Result<()> {
+    if holder_config.owner == &crate::ID && !holder_config.data_is_empty() {
+        let mut user_config = UserConfig::try_deserialize(&mut &holder_config.data.borrow()[..])?;
+        user_config.require_active()?;
+        user_config.record_activity(now);
+        user_config.try_serialize(&mut &mut holder_config.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+/// Freeze or thaw the owner's share token account so minted shares can't be moved
+/// to another wallet while the owner is frozen. A no-op when the share accounts are
+/// left out or the account is already in that state.
+pub(crate) fn set_owner_shares_frozen(accounts: &ManageUser, freeze: bool) -> Result<()> {
+    let (Some(share_pool), Some(share_mint), Some(owner_shares), Some(token_program)) = (
+        accounts.share_pool.as_ref(),
+        accounts.share_mint.as_ref(),
+        accounts.owner_shares.as_ref(),
+        accounts.token_program.as_ref(),
+    ) else {
+        return Ok(());
+    };
+    if owner_shares.is_frozen() == freeze {
+        return Ok(());
+    }
+    set_shares_frozen(share_pool, share_mint, owner_shares, token_program, freeze)
+}
+
+/// Freeze or thaw a share token account, signing as the share pool (the share mint's
+/// freeze authority)
+pub(crate) fn set_shares_frozen<'info>(
+    share_pool: &Account<'info, SharePool>,
+    share_mint: &InterfaceAccount<'info, Mint>,
+    shares: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    freeze: bool,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[b"share_pool".as_ref(), &[share_pool.bump]];
+    let signer_seeds = &[seeds];
+    if freeze {
+        token_interface::freeze_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            FreezeAccount {
+                account: shares.to_account_info(),
+                mint: share_mint.to_account_info(),
+                authority: share_pool.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    } else {
+        token_interface::thaw_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            ThawAccount {
+                account: shares.to_account_info(),
+                mint: share_mint.to_account_info(),
+                authority: share_pool.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    }
+}
+
+/// Retire a user's whole pooled position and pay its SOL value, less the platform
+/// fee, from the share vault to `to`. Inheritance and frozen withdrawals close out
+/// the pool this way since the owner isn't around to move it first. Returns the
+/// payout and the fee (both 0 without a pooled position).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn settle_pooled_position<'info>(
+    user_config: &mut UserConfig,
+    share_pool: &mut Option<Account<'info, SharePool>>,
+    share_mint: &Option<Box<InterfaceAccount<'info, Mint>>>,
+    share_vault: &Option<SystemAccount<'info>>,
+    to: AccountInfo<'info>,
+    treasury: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    fee_basis_points: u16,
+) -> Result<(u64, u64)> {
+    let shares = user_config.pooled_shares;
+    if shares == 0 {
+        return Ok((0, 0));
+    }
+    let (Some(share_pool), Some(share_mint), Some(share_vault)) = (
+        share_pool.as_mut(),
+        share_mint.as_ref(),
+        share_vault.as_ref(),
+    ) else {
+        return err!(ErrorCode::SharePoolAccountsRequired);
+    };
+
+    let supply = share_pool.total_shares(share_mint.supply)?;
+    let lamports = share_pool.retire(shares, supply)?;
+    share_pool.require_liquid(share_vault.lamports(), lamports)?;
+    share_pool.pooled_shares -= shares;
+    user_config.pooled_shares = 0;
+    user_config.record_withdrawn(lamports);
+
+    let platform_fee = calculate_fee(lamports, fee_basis_points)?;
+    let payout = lamports
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    let seeds: &[&[u8]] = &[b"share_vault".as_ref(), &[share_pool.vault_bump]];
+    transfer_from_pda(share_vault, to, system_program, seeds, payout)?;
+    transfer_from_pda(share_vault, treasury, system_program, seeds, platform_fee)?;
+    Ok((payout, platform_fee))
+}
+
+// Account Structures
+#[derive(Accounts)]
+pub struct RecoverWithdrawalTicket<'info> {
+    /// CHECK: Bound to old_user_config through its seeds
+    pub old_owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"user_config", old_owner.key().as_ref()],
+        bump = old_user_config.bump
+    )]
+    pub old_user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_ticket", ticket.id.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        constraint = ticket.owner == old_owner.key() @ ErrorCode::Unauthorized
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardPool<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RewardPool::INIT_SPACE,
+        seeds = [b"reward_pool"],
+        bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    #[account(
+        seeds = [b"reward_funds"],
+        bump
+    )]
+    /// CHECK: Reward SOL holder PDA
+    pub reward_funds: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRewards<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_funds"],
+        bump = reward_pool.funds_bump
+    )]
+    /// CHECK: Reward SOL holder PDA
+    pub reward_funds: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"vault", user.key().as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: User's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_funds"],
+        bump = reward_pool.funds_bump
+    )]
+    /// CHECK: Reward SOL holder PDA
+    pub reward_funds: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user_config.owner.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"vault", user_config.owner.as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: Owner's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user_config.owner)].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSharePool<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SharePool::INIT_SPACE,
+        seeds = [b"share_pool"],
+        bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"share_mint"],
+        bump,
+        mint::decimals = SHARE_DECIMALS,
+        mint::authority = share_pool,
+        mint::freeze_authority = share_pool,
+        mint::token_program = token_program
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        mut,
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump = share_pool.vault_bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_shares: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Holder's savings account, if they have one; checked not to be frozen
+    pub user_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        mut,
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump = share_pool.vault_bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+        token::token_program = token_program
+    )]
+    pub user_shares: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueShareYield<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump = share_pool.vault_bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PooledVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump = share_pool.vault_bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: User's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump = share_pool.vault_bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: SystemAccount<'info>,
+
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeWithdrawalQueue<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + WithdrawalQueue::INIT_SPACE,
+        seeds = [b"withdrawal_queue"],
+        bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawalNotice<'info> {
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + WithdrawalTicket::INIT_SPACE,
+        seeds = [
+            b"withdrawal_ticket",
+            withdrawal_queue.tail.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithdrawal<'info> {
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump = share_pool.vault_bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_ticket", ticket.id.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    #[account(mut)]
+    /// CHECK: Ticket owner, receives the payout and the ticket's rent
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"user_config", owner.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Ticket owner's savings account, if they have one; checked not to be frozen
+    pub owner_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the ticket owner or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestShareWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Holder's savings account, if they have one; checked not to be frozen
+    pub user_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        mut,
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+        token::token_program = token_program
+    )]
+    pub user_shares: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + WithdrawalTicket::INIT_SPACE,
+        seeds = [
+            b"withdrawal_ticket",
+            withdrawal_queue.tail.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump = share_pool.bump
+    )]
+    pub share_pool: Account<'info, SharePool>,
+
+    #[account(
+        mut,
+        seeds = [b"share_mint"],
+        bump = share_pool.mint_bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_ticket", ticket.id.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        has_one = owner
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    /// CHECK: Ticket owner
+    pub owner: UncheckedAccount<'info>,
+
+    /// Owner's savings account; required for tickets from a pooled position
+    #[account(
+        mut,
+        seeds = [b"user_config", owner.key().as_ref()],
+        bump
+    )]
+    pub owner_config: Option<Account<'info, UserConfig>>,
+
+    /// Owner's share token account; required for tickets from minted shares
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = owner,
+        token::token_program = token_program
+    )]
+    pub owner_shares: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    /// Ticket owner or risk manager
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
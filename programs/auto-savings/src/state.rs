@@ -0,0 +1,1217 @@
+//! Program-owned account layouts and events
+
+use crate::*;
+
+/// Realloc a program-owned account up to `new_len`, zero-filling the new bytes and
+/// topping up rent from `payer`. Accounts already at least `new_len` are left alone.
+pub(crate) fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if rent_due > 0 {
+        let rent_transfer = CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            },
+        );
+        transfer(rent_transfer, rent_due)?;
+    }
+    account.realloc(new_len, true)?;
+    Ok(())
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TreasuryConfig {
+    pub authority: Pubkey,
+    pub bump: u8,
+    /// Original kill switch, superseded by `pause_mask`. Kept so deployed accounts
+    /// still decode; `migrate_treasury_config` folds it into the mask.
+    pub is_paused: bool,
+    /// Global TVL as of the last `aggregate_stats`
+    pub total_tvl: u64,
+    pub tvl_cap: u64,
+    // Fields below were appended after launch; never reorder or insert above them
+    /// Layout version; see `migrate_treasury_config`
+    pub version: u8,
+    /// Cached `treasury_vault` bump so hot paths skip `find_program_address`
+    pub treasury_bump: u8,
+    pub fee_basis_points: u16,
+    /// Bitmask of PAUSE_* flags
+    pub pause_mask: u8,
+    /// Withdrawals auto-unpause at this timestamp even if the flag is still set
+    pub withdrawals_paused_until: i64,
+    /// Launch-era vault balances not yet moved into a stats shard by `migrate_user_config`
+    pub unmigrated_tvl: u64,
+    /// Per-user vault balance cap applied unless overridden (0 = unlimited)
+    pub default_max_vault_balance: u64,
+    /// Per-user deposits allowed per DEPOSIT_WINDOW_SECONDS (0 = unlimited)
+    pub default_max_daily_deposit: u64,
+    /// When set, this key may co-sign `initialize_user` to admit a new user
+    pub onboarding_verifier: Option<Pubkey>,
+    /// When set, users may self-admit with a Merkle proof against this root
+    pub allowlist_root: Option<[u8; 32]>,
+    /// Prefix of achievement NFT metadata URIs (empty = achievements can't be claimed yet)
+    #[max_len(MAX_ACHIEVEMENT_URI_BASE_LEN)]
+    pub achievement_uri_base: String,
+}
+
+impl TreasuryConfig {
+    pub(crate) fn is_paused(&self, flag: u8, now: i64) -> bool {
+        self.active_pause_mask(now) & flag != 0
+    }
+
+    /// `pause_mask` without a PAUSE_WITHDRAWALS bit whose window has lapsed
+    pub(crate) fn active_pause_mask(&self, now: i64) -> u8 {
+        if now < self.withdrawals_paused_until {
+            self.pause_mask
+        } else {
+            self.pause_mask & !PAUSE_WITHDRAWALS
+        }
+    }
+
+    pub(crate) fn set_pause_mask(
+        &mut self,
+        pause_mask: u8,
+        withdrawal_pause_seconds: i64,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            pause_mask & !ALL_PAUSE_FLAGS == 0,
+            ErrorCode::InvalidPauseFlags
+        );
+
+        // A lapsed withdrawal bit is cleared here rather than left to re-arm later
+        let lapsed = self.pause_mask & !self.active_pause_mask(now) != 0;
+        self.pause_mask = self.active_pause_mask(now);
+
+        let mut pause_mask = pause_mask;
+        if lapsed && withdrawal_pause_seconds == 0 {
+            // The caller echoed the stale bit back without asking for a new window
+            pause_mask &= !PAUSE_WITHDRAWALS;
+        }
+
+        let pausing_withdrawals = pause_mask & PAUSE_WITHDRAWALS != 0;
+        if !pausing_withdrawals {
+            // Lifting the pause early ends the window immediately
+            if self.withdrawals_paused_until > now {
+                self.withdrawals_paused_until = now;
+            }
+        } else if !self.is_paused(PAUSE_WITHDRAWALS, now) {
+            // A fresh withdrawal pause: bounded duration, and no back-to-back renewals
+            require!(
+                withdrawal_pause_seconds > 0
+                    && withdrawal_pause_seconds <= MAX_WITHDRAWAL_PAUSE_SECONDS,
+                ErrorCode::WithdrawalPauseTooLong
+            );
+            let cooldown_ends = self
+                .withdrawals_paused_until
+                .checked_add(WITHDRAWAL_PAUSE_COOLDOWN_SECONDS)
+                .ok_or(ErrorCode::Overflow)?;
+            require!(
+                self.withdrawals_paused_until == 0 || now >= cooldown_ends,
+                ErrorCode::WithdrawalPauseCooldown
+            );
+            self.withdrawals_paused_until = now
+                .checked_add(withdrawal_pause_seconds)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        // An already-running withdrawal pause keeps its original expiry
+
+        self.pause_mask = pause_mask;
+        log_msg!(
+            "Pause mask set to {:#07b} (withdrawals paused until {})",
+            pause_mask,
+            self.withdrawals_paused_until
+        );
+        Ok(())
+    }
+
+    pub(crate) fn set_tvl_cap(&mut self, new_cap: u64) -> Result<()> {
+        require!(new_cap >= self.total_tvl, ErrorCode::InvalidAmount);
+        log_msg!(
+            "TVL cap updated from {} to {} lamports",
+            self.tvl_cap,
+            new_cap
+        );
+        self.tvl_cap = new_cap;
+        Ok(())
+    }
+
+    pub(crate) fn set_fee_basis_points(&mut self, fee_basis_points: u16) -> Result<()> {
+        require!(
+            fee_basis_points <= MAX_FEE_BASIS_POINTS,
+            ErrorCode::FeeTooHigh
+        );
+        log_msg!(
+            "Platform fee updated from {} to {} bps",
+            self.fee_basis_points,
+            fee_basis_points
+        );
+        self.fee_basis_points = fee_basis_points;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserConfig {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub vault_bump: u8,
+    // Fields below were appended after launch; never reorder or insert above them
+    /// Layout version; see `migrate_user_config`
+    pub version: u8,
+    /// Can claim the vault after `inactivity_period` (default pubkey = none)
+    pub beneficiary: Pubkey,
+    pub inactivity_period: i64,
+    /// Last time the owner signed any instruction
+    pub last_activity_ts: i64,
+    /// Percent of each hooked Token-2022 transfer owed to savings (0 = off)
+    pub savings_rate: u8,
+    /// Risk-role overrides of the TreasuryConfig defaults (0 = unlimited)
+    pub max_vault_balance_override: Option<u64>,
+    pub max_daily_deposit_override: Option<u64>,
+    /// Deposits counted against the daily limit, decaying linearly to zero over
+    /// DEPOSIT_WINDOW_SECONDS from `deposit_window_ts`, when they were last updated
+    pub deposit_window_ts: i64,
+    pub deposited_in_window: u64,
+    pub status: UserStatus,
+    /// While frozen, withdrawals can only go here
+    pub recovery_destination: Pubkey,
+    /// Consecutive STREAK_PERIOD_SECONDS periods with an owner deposit
+    pub streak: u32,
+    pub best_streak: u32,
+    pub last_deposit_period: i64,
+    /// Non-transferable; redeemed for fee credit
+    pub reward_points: u64,
+    /// Lamports of platform fees waived on upcoming deposits/withdrawals
+    pub fee_credit: u64,
+    /// Target vault balance for the goal-reached achievement (0 = none)
+    pub savings_goal: u64,
+    /// Principal (`net_deposited`) currently earning rewards, as of the last sync
+    pub reward_balance: u64,
+    /// `reward_balance` times the stats shard's `acc_reward_per_share` at the last sync
+    pub reward_debt: u128,
+    /// Accrued rewards not yet claimed
+    pub pending_rewards: u64,
+    /// Shares of the pooled vault held in pooled mode (not minted as tokens)
+    pub pooled_shares: u64,
+    /// Lamports deposited into the vault or pooled position less lamports taken out.
+    /// Milestones use this rather than the vault balance, which anyone can inflate by
+    /// sending lamports straight to the vault.
+    pub net_deposited: u64,
+}
+
+impl UserConfig {
+    /// Reset the inheritance timer; call from every owner-signed instruction
+    pub(crate) fn record_activity(&mut self, now: i64) {
+        self.last_activity_ts = now;
+    }
+
+    /// Extend or restart the deposit streak; the first qualifying deposit in each
+    /// period earns points
+    pub(crate) fn record_streak(&mut self, deposited: u64, now: i64) -> Result<()> {
+        if deposited < MIN_STREAK_DEPOSIT_LAMPORTS {
+            return Ok(());
+        }
+        let period = now / STREAK_PERIOD_SECONDS;
+        if self.streak > 0 && period == self.last_deposit_period {
+            return Ok(());
+        }
+        self.streak = if self.streak > 0 && period == self.last_deposit_period + 1 {
+            self.streak.checked_add(1).ok_or(ErrorCode::Overflow)?
+        } else {
+            1
+        };
+        self.best_streak = self.best_streak.max(self.streak);
+        self.last_deposit_period = period;
+
+        let multiplier = (self.streak as u64).min(MAX_STREAK_MULTIPLIER);
+        self.reward_points = self
+            .reward_points
+            .checked_add(POINTS_PER_STREAK_PERIOD * multiplier)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Count lamports that reached the owner's vault or pooled position as saved
+    pub(crate) fn record_saved(&mut self, amount: u64) -> Result<()> {
+        self.net_deposited = self
+            .net_deposited
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Take lamports leaving the vault or pooled position, fee included, off the
+    /// saved total
+    pub(crate) fn record_withdrawn(&mut self, amount: u64) {
+        self.net_deposited = self.net_deposited.saturating_sub(amount);
+    }
+
+    /// Settle rewards accrued on the old stake and restake `net_deposited`, which
+    /// covers the pooled position and ignores lamports sent straight to the vault.
+    /// Call with the owner's stats shard whenever `net_deposited` changes.
+    pub(crate) fn sync_rewards(&mut self, stats_shard: &mut StatsShard) -> Result<()> {
+        self.stake_rewards(stats_shard, self.net_deposited)
+    }
+
+    /// Settle rewards accrued on the old stake and start earning on `staked`
+    pub(crate) fn stake_rewards(
+        &mut self,
+        stats_shard: &mut StatsShard,
+        staked: u64,
+    ) -> Result<()> {
+        let accrued = (self.reward_balance as u128)
+            .checked_mul(stats_shard.acc_reward_per_share)
+            .ok_or(ErrorCode::Overflow)?;
+        let earned = accrued.saturating_sub(self.reward_debt) / REWARD_PRECISION;
+        self.pending_rewards = self
+            .pending_rewards
+            .checked_add(earned as u64)
+            .ok_or(ErrorCode::Overflow)?;
+
+        stats_shard.reward_staked = stats_shard
+            .reward_staked
+            .checked_sub(self.reward_balance)
+            .ok_or(ErrorCode::Overflow)?
+            .checked_add(staked)
+            .ok_or(ErrorCode::Overflow)?;
+        self.reward_balance = staked;
+        self.reward_debt = (staked as u128)
+            .checked_mul(stats_shard.acc_reward_per_share)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Spend fee credit against `fee`, returning what is still owed
+    pub(crate) fn apply_fee_credit(&mut self, fee: u64) -> u64 {
+        let waived = fee.min(self.fee_credit);
+        self.fee_credit -= waived;
+        fee - waived
+    }
+
+    pub(crate) fn require_active(&self) -> Result<()> {
+        match self.status {
+            UserStatus::Active => Ok(()),
+            UserStatus::Frozen => err!(ErrorCode::AccountFrozen),
+            UserStatus::Recovered => err!(ErrorCode::AccountRecovered),
+        }
+    }
+
+    /// Frozen funds can be pushed to the recovery destination by the owner or, since a
+    /// sanctioned or compromised owner may never sign, by a risk manager
+    pub(crate) fn require_frozen_withdrawal_authority(
+        &self,
+        roles: &Roles,
+        authority: &Pubkey,
+    ) -> Result<()> {
+        require!(
+            self.status == UserStatus::Frozen,
+            ErrorCode::AccountNotFrozen
+        );
+        require!(
+            *authority == self.owner || roles.has_role(authority, ROLE_RISK_MANAGER),
+            ErrorCode::Unauthorized
+        );
+        Ok(())
+    }
+
+    /// Only the owner a vault was recovered to may move what the old owner left behind
+    pub(crate) fn require_recovered_to(&self, new_owner: &Pubkey) -> Result<()> {
+        require!(
+            self.status == UserStatus::Recovered && self.recovery_destination == *new_owner,
+            ErrorCode::InvalidRecoveryOwner
+        );
+        Ok(())
+    }
+
+    /// Check a deposit against the freeze status and per-user limits, then count it
+    /// against the daily limit
+    pub(crate) fn record_deposit(
+        &mut self,
+        treasury_config: &TreasuryConfig,
+        vault_balance: u64,
+        amount: u64,
+        amount_after_fee: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.require_active()?;
+
+        let max_vault_balance = self
+            .max_vault_balance_override
+            .unwrap_or(treasury_config.default_max_vault_balance);
+        if max_vault_balance > 0 {
+            let new_vault_balance = vault_balance
+                .checked_add(amount_after_fee)
+                .ok_or(ErrorCode::Overflow)?;
+            require!(
+                new_vault_balance <= max_vault_balance,
+                ErrorCode::UserLimitExceeded
+            );
+        }
+
+        self.record_velocity(treasury_config, amount, now)
+    }
+
+    /// Count lamports leaving the owner's wallet against their daily deposit limit.
+    /// The window rolls: earlier deposits decay linearly rather than resetting at a
+    /// boundary, so the limit can't be doubled by depositing either side of one.
+    pub(crate) fn record_velocity(
+        &mut self,
+        treasury_config: &TreasuryConfig,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.require_active()?;
+
+        let elapsed = now
+            .saturating_sub(self.deposit_window_ts)
+            .clamp(0, DEPOSIT_WINDOW_SECONDS);
+        let still_counted = (self.deposited_in_window as u128)
+            .checked_mul((DEPOSIT_WINDOW_SECONDS - elapsed) as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / DEPOSIT_WINDOW_SECONDS as u128;
+        let deposited = (still_counted as u64)
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let max_daily_deposit = self
+            .max_daily_deposit_override
+            .unwrap_or(treasury_config.default_max_daily_deposit);
+        require!(
+            max_daily_deposit == 0 || deposited <= max_daily_deposit,
+            ErrorCode::UserLimitExceeded
+        );
+        self.deposit_window_ts = now;
+        self.deposited_in_window = deposited;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AdminCouncil {
+    #[max_len(MAX_COUNCIL_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timelock_seconds: i64,
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl AdminCouncil {
+    pub(crate) fn is_member(&self, key: &Pubkey) -> bool {
+        self.members.contains(key)
+    }
+
+    pub(crate) fn set_membership(
+        &mut self,
+        members: Vec<Pubkey>,
+        threshold: u8,
+        timelock_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_COUNCIL_MEMBERS,
+            ErrorCode::InvalidCouncil
+        );
+        require!(
+            threshold > 0 && threshold as usize <= members.len(),
+            ErrorCode::InvalidCouncil
+        );
+        require!(timelock_seconds >= 0, ErrorCode::InvalidCouncil);
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), ErrorCode::InvalidCouncil);
+        }
+
+        self.members = members;
+        self.threshold = threshold;
+        self.timelock_seconds = timelock_seconds;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AdminProposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    #[max_len(MAX_COUNCIL_MEMBERS)]
+    pub approvals: Vec<Pubkey>,
+    pub created_at: i64,
+    /// Timestamp the approval threshold was reached (0 while pending)
+    pub approved_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum AdminAction {
+    SetPauseMask {
+        pause_mask: u8,
+        withdrawal_pause_seconds: i64,
+    },
+    SetTvlCap {
+        new_cap: u64,
+    },
+    SetFeeBasisPoints {
+        fee_basis_points: u16,
+    },
+    SetSuperAdmin {
+        super_admin: Pubkey,
+    },
+    SetRoles {
+        member: Pubkey,
+        role_flags: u8,
+    },
+    /// Replace the council's members, threshold and timelock
+    SetCouncil {
+        #[max_len(MAX_COUNCIL_MEMBERS)]
+        members: Vec<Pubkey>,
+        threshold: u8,
+        timelock_seconds: i64,
+    },
+    WithdrawTreasury {
+        amount: u64,
+        destination: Pubkey,
+    },
+}
+
+impl AdminAction {
+    /// Only tightening the pause mask is allowed to bypass the timelock
+    pub(crate) fn is_emergency(&self, current_pause_mask: u8) -> bool {
+        match self {
+            AdminAction::SetPauseMask { pause_mask, .. } => {
+                *pause_mask != current_pause_mask
+                    && pause_mask & current_pause_mask == current_pause_mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Roles {
+    /// Holds every role and is the only key that can assign them
+    pub super_admin: Pubkey,
+    #[max_len(MAX_ROLE_MEMBERS)]
+    pub members: Vec<RoleMember>,
+    pub bump: u8,
+}
+
+impl Roles {
+    pub(crate) fn has_role(&self, key: &Pubkey, role: u8) -> bool {
+        *key == self.super_admin
+            || self
+                .members
+                .iter()
+                .any(|m| m.key == *key && m.roles & role == role)
+    }
+
+    pub(crate) fn require_role(&self, key: &Pubkey, role: u8) -> Result<()> {
+        require!(self.has_role(key, role), ErrorCode::MissingRole);
+        Ok(())
+    }
+
+    /// Grant or replace a member's roles; 0 removes the member
+    pub(crate) fn set_member_roles(&mut self, member: Pubkey, role_flags: u8) -> Result<()> {
+        require!(role_flags & !ALL_ROLES == 0, ErrorCode::InvalidRoles);
+
+        match self.members.iter().position(|m| m.key == member) {
+            Some(index) if role_flags == 0 => {
+                self.members.remove(index);
+            }
+            Some(index) => self.members[index].roles = role_flags,
+            None if role_flags == 0 => {}
+            None => {
+                require!(
+                    self.members.len() < MAX_ROLE_MEMBERS,
+                    ErrorCode::InvalidRoles
+                );
+                self.members.push(RoleMember {
+                    key: member,
+                    roles: role_flags,
+                });
+            }
+        }
+
+        log_msg!("Roles for {} set to {:#04b}", member, role_flags);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RoleMember {
+    pub key: Pubkey,
+    pub roles: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RecoveryConfig {
+    pub owner: Pubkey,
+    #[max_len(MAX_RECOVERY_GUARDIANS)]
+    pub guardians: Vec<Pubkey>,
+    pub threshold: u8,
+    pub delay_seconds: i64,
+    /// Proposed new owner (default pubkey when no recovery is pending)
+    pub pending_owner: Pubkey,
+    #[max_len(MAX_RECOVERY_GUARDIANS)]
+    pub approvals: Vec<Pubkey>,
+    pub initiated_at: i64,
+    /// Timestamp the guardian threshold was reached; the delay runs from here
+    pub approved_at: i64,
+    pub bump: u8,
+}
+
+impl RecoveryConfig {
+    pub(crate) fn clear_pending(&mut self) {
+        self.pending_owner = Pubkey::default();
+        self.approvals = Vec::new();
+        self.initiated_at = 0;
+        self.approved_at = 0;
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawAllowance {
+    pub owner: Pubkey,
+    pub spender: Pubkey,
+    /// Only account withdrawals under this allowance can be sent to
+    pub destination: Pubkey,
+    pub amount_per_period: u64,
+    pub period_seconds: i64,
+    pub period_start: i64,
+    pub withdrawn_in_period: u64,
+    pub bump: u8,
+}
+
+impl WithdrawAllowance {
+    /// Start a fresh period (aligned to the original schedule) once the current one ends
+    pub(crate) fn roll_period(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.period_start);
+        if elapsed >= self.period_seconds {
+            let skipped = elapsed / self.period_seconds * self.period_seconds;
+            self.period_start = self
+                .period_start
+                .checked_add(skipped)
+                .ok_or(ErrorCode::Overflow)?;
+            self.withdrawn_in_period = 0;
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MatchingProgram {
+    pub sponsor: Pubkey,
+    pub program_id: u64,
+    /// Match paid per deposited lamport, in basis points (10,000 = 1:1)
+    pub match_basis_points: u16,
+    /// Most a single user can be matched per period
+    pub per_user_cap: u64,
+    pub period_seconds: i64,
+    pub total_matched: u64,
+    pub bump: u8,
+    pub funds_bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MatchingParticipant {
+    pub matching_program: Pubkey,
+    pub user: Pubkey,
+    pub period_start: i64,
+    pub matched_in_period: u64,
+    pub bump: u8,
+}
+
+impl MatchingParticipant {
+    /// Start a fresh period (aligned to the join time) once the current one ends
+    pub(crate) fn roll_period(&mut self, now: i64, period_seconds: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.period_start);
+        if elapsed >= period_seconds {
+            let skipped = elapsed / period_seconds * period_seconds;
+            self.period_start = self
+                .period_start
+                .checked_add(skipped)
+                .ok_or(ErrorCode::Overflow)?;
+            self.matched_in_period = 0;
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct JointVault {
+    pub vault_id: u64,
+    pub creator: Pubkey,
+    #[max_len(MAX_JOINT_MEMBERS)]
+    pub members: Vec<JointMember>,
+    pub policy: JointWithdrawalPolicy,
+    pub proposal_count: u64,
+    pub bump: u8,
+    pub funds_bump: u8,
+}
+
+impl JointVault {
+    pub(crate) fn member_index(&self, key: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|m| m.key == *key)
+            .ok_or_else(|| error!(ErrorCode::NotJointMember))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct JointMember {
+    pub key: Pubkey,
+    /// Net lamports deposited (after platform fee)
+    pub contributed: u64,
+    /// Lamports taken out by this member, platform fees included
+    pub withdrawn: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub enum JointWithdrawalPolicy {
+    /// Any member can withdraw any amount
+    AnyMember,
+    /// Withdrawals need `threshold` member approvals
+    Threshold { threshold: u8 },
+    /// Each member can withdraw up to their own net contribution, fees included
+    Proportional,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct JointWithdrawalProposal {
+    pub joint_vault: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+    pub destination: Pubkey,
+    #[max_len(MAX_JOINT_MEMBERS)]
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+/// Circle pots are pass-through escrow and are not counted toward TVL
+#[account]
+#[derive(InitSpace)]
+pub struct SavingsCircle {
+    pub circle_id: u64,
+    pub creator: Pubkey,
+    /// Payout order: member `i` receives the pot in round `i`
+    #[max_len(MAX_CIRCLE_MEMBERS)]
+    pub members: Vec<CircleMember>,
+    pub contribution: u64,
+    /// Collateral each member posts on joining
+    pub collateral: u64,
+    pub round_duration: i64,
+    pub late_penalty_basis_points: u16,
+    pub current_round: u32,
+    pub round_started_at: i64,
+    /// Lamports contributed to the current round so far
+    pub pot: u64,
+    pub status: CircleStatus,
+    pub bump: u8,
+    pub funds_bump: u8,
+}
+
+impl SavingsCircle {
+    pub(crate) fn member_index(&self, key: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|m| m.key == *key)
+            .ok_or_else(|| error!(ErrorCode::NotCircleMember))
+    }
+
+    pub(crate) fn round_deadline(&self) -> Result<i64> {
+        Ok(self
+            .round_started_at
+            .checked_add(self.round_duration)
+            .ok_or(ErrorCode::Overflow)?)
+    }
+
+    pub(crate) fn late_penalty(&self) -> Result<u64> {
+        calculate_fee(self.contribution, self.late_penalty_basis_points)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CircleMember {
+    pub key: Pubkey,
+    pub joined: bool,
+    /// Collateral still held for this member
+    pub collateral: u64,
+    pub paid_current_round: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CircleStatus {
+    Forming,
+    Active,
+    Completed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum UserStatus {
+    Active,
+    /// Sanctions hit or suspected compromise; see `freeze_user`
+    Frozen,
+    /// Ownership moved to `recovery_destination` by `execute_recovery`
+    Recovered,
+}
+
+/// Slice of per-user vault TVL and reward stake. Zero-copy so the hot
+/// deposit/withdraw paths don't pay for (de)serialization.
+///
+/// Users sync rewards against their shard rather than the global `RewardPool`, so
+/// deposits in different shards never write-lock the same account. The shard settles
+/// with the pool in `aggregate_stats`, `sync_rewards` and `claim_rewards`.
+#[account(zero_copy)]
+pub struct StatsShard {
+    /// Rewards per staked lamport credited to this shard, scaled by REWARD_PRECISION
+    pub acc_reward_per_share: u128,
+    /// `RewardPool::acc_reward_per_share` at the last settlement
+    pub pool_acc_snapshot: u128,
+    pub total_tvl: u64,
+    /// Most TVL deposits may bring this shard to; set by `aggregate_stats`
+    pub tvl_quota: u64,
+    /// Sum of `UserConfig::reward_balance` for the shard's users
+    pub reward_staked: u64,
+    /// Stake the pool has on record for this shard since the last settlement
+    pub pool_staked: u64,
+    pub index: u8,
+    pub bump: u8,
+    pub _padding: [u8; 14],
+}
+
+impl StatsShard {
+    pub(crate) fn add_tvl(&mut self, amount: u64) -> Result<()> {
+        self.total_tvl = self
+            .total_tvl
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Count new deposits, which must fit in the shard's quota
+    pub(crate) fn add_deposit(&mut self, amount: u64) -> Result<()> {
+        self.add_tvl(amount)?;
+        require!(self.total_tvl <= self.tvl_quota, ErrorCode::TvlCapExceeded);
+        Ok(())
+    }
+
+    pub(crate) fn add_quota(&mut self, amount: u64) -> Result<()> {
+        self.tvl_quota = self
+            .tvl_quota
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Give up to `amount` of quota to another slot, returning how much was released
+    pub(crate) fn release_quota(&mut self, amount: u64) -> u64 {
+        let released = amount.min(self.tvl_quota);
+        self.tvl_quota -= released;
+        released
+    }
+
+    pub(crate) fn sub_tvl(&mut self, amount: u64) -> Result<()> {
+        self.total_tvl = self
+            .total_tvl
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Collect what the shard's stake on record earned in the pool since the last
+    /// settlement, spread it over the shard's current stake, and put that stake on
+    /// record instead. Rewards earned while nobody in the shard is staked stay in the
+    /// reward funds.
+    pub(crate) fn settle_rewards(&mut self, reward_pool: &mut RewardPool, now: i64) -> Result<()> {
+        reward_pool.update(now)?;
+        if self.reward_staked > 0 {
+            let earned = reward_pool
+                .acc_reward_per_share
+                .checked_sub(self.pool_acc_snapshot)
+                .ok_or(ErrorCode::Overflow)?
+                .checked_mul(self.pool_staked as u128)
+                .ok_or(ErrorCode::Overflow)?;
+            self.acc_reward_per_share = self
+                .acc_reward_per_share
+                .checked_add(earned / self.reward_staked as u128)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        self.pool_acc_snapshot = reward_pool.acc_reward_per_share;
+
+        reward_pool.total_staked = reward_pool
+            .total_staked
+            .checked_sub(self.pool_staked)
+            .ok_or(ErrorCode::Overflow)?
+            .checked_add(self.reward_staked)
+            .ok_or(ErrorCode::Overflow)?;
+        self.pool_staked = self.reward_staked;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Milestone {
+    FirstDeposit,
+    OneSolSaved,
+    ThirtyDayStreak,
+    GoalReached,
+}
+
+impl Milestone {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Milestone::FirstDeposit => "First Deposit",
+            Milestone::OneSolSaved => "1 SOL Saved",
+            Milestone::ThirtyDayStreak => "30-Day Streak",
+            Milestone::GoalReached => "Goal Reached",
+        }
+    }
+
+    /// File name of the badge's metadata under `achievement_uri_base`
+    pub(crate) fn slug(&self) -> &'static str {
+        match self {
+            Milestone::FirstDeposit => "first-deposit",
+            Milestone::OneSolSaved => "one-sol-saved",
+            Milestone::ThirtyDayStreak => "thirty-day-streak",
+            Milestone::GoalReached => "goal-reached",
+        }
+    }
+}
+
+/// Claim record; one per user and milestone
+#[account]
+#[derive(InitSpace)]
+pub struct Achievement {
+    pub owner: Pubkey,
+    pub milestone: Milestone,
+    pub mint: Pubkey,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+/// Reward-per-share accumulator for fee income returned to savers
+#[account]
+#[derive(InitSpace)]
+pub struct RewardPool {
+    /// Rewards per staked lamport since launch, scaled by REWARD_PRECISION
+    pub acc_reward_per_share: u128,
+    /// Sum of every stats shard's `pool_staked`
+    pub total_staked: u64,
+    /// Lamports streamed per second during the current epoch
+    pub reward_rate: u64,
+    pub last_update_ts: i64,
+    pub epoch_end_ts: i64,
+    pub epoch: u64,
+    pub bump: u8,
+    pub funds_bump: u8,
+}
+
+impl RewardPool {
+    /// Accrue the rewards streamed since the last update. Time with nobody staked
+    /// streams nothing; those lamports stay in the reward funds.
+    pub(crate) fn update(&mut self, now: i64) -> Result<()> {
+        let until = now.min(self.epoch_end_ts);
+        if until > self.last_update_ts && self.total_staked > 0 {
+            let streamed = ((until - self.last_update_ts) as u128)
+                .checked_mul(self.reward_rate as u128)
+                .ok_or(ErrorCode::Overflow)?;
+            self.acc_reward_per_share = self
+                .acc_reward_per_share
+                .checked_add(
+                    streamed
+                        .checked_mul(REWARD_PRECISION)
+                        .ok_or(ErrorCode::Overflow)?
+                        / self.total_staked as u128,
+                )
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        self.last_update_ts = now;
+        Ok(())
+    }
+}
+
+/// Pooled vault backing both the savings share token and pooled-mode users. Share
+/// price is `total_assets / (share_mint.supply + pooled_shares)` and only rises as
+/// yield accrues.
+#[account]
+#[derive(InitSpace)]
+pub struct SharePool {
+    /// Lamports in the share vault owed to shareholders (excludes its rent reserve)
+    pub total_assets: u64,
+    /// Shares held in pooled mode, recorded in `UserConfig::pooled_shares` instead of minted
+    pub pooled_shares: u64,
+    /// Lamports owed to open withdrawal tickets, still held in the share vault
+    pub queued: u64,
+    /// Most TVL deposits may bring the pool to; set by `aggregate_stats`
+    pub tvl_quota: u64,
+    pub bump: u8,
+    pub mint_bump: u8,
+    pub vault_bump: u8,
+}
+
+impl SharePool {
+    /// Pool TVL: lamports backing shares plus lamports reserved for the queue
+    pub(crate) fn tvl(&self) -> Result<u64> {
+        self.total_assets
+            .checked_add(self.queued)
+            .ok_or(ErrorCode::Overflow.into())
+    }
+
+    /// Deposits may not take the pool past its quota
+    pub(crate) fn require_within_quota(&self) -> Result<()> {
+        require!(self.tvl()? <= self.tvl_quota, ErrorCode::TvlCapExceeded);
+        Ok(())
+    }
+
+    pub(crate) fn add_quota(&mut self, amount: u64) -> Result<()> {
+        self.tvl_quota = self
+            .tvl_quota
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Give up to `amount` of quota to another slot, returning how much was released
+    pub(crate) fn release_quota(&mut self, amount: u64) -> u64 {
+        let released = amount.min(self.tvl_quota);
+        self.tvl_quota -= released;
+        released
+    }
+
+    /// Instant withdrawals may only use vault lamports not reserved for the queue
+    pub(crate) fn require_liquid(&self, vault_lamports: u64, amount: u64) -> Result<()> {
+        let liquid = vault_lamports
+            .saturating_sub(Rent::get()?.minimum_balance(0))
+            .saturating_sub(self.queued);
+        require!(liquid >= amount, ErrorCode::InsufficientLiquidity);
+        Ok(())
+    }
+
+    /// Outstanding shares of both kinds
+    pub(crate) fn total_shares(&self, mint_supply: u64) -> Result<u64> {
+        mint_supply
+            .checked_add(self.pooled_shares)
+            .ok_or(ErrorCode::Overflow.into())
+    }
+
+    /// Add `lamports` to the pool, returning the shares they buy
+    pub(crate) fn issue(&mut self, lamports: u64, supply: u64) -> Result<u64> {
+        let shares = self.lamports_to_shares(lamports, supply)?;
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        self.total_assets = self
+            .total_assets
+            .checked_add(lamports)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(shares)
+    }
+
+    /// Take `shares` worth of lamports out of the pool, returning the amount
+    pub(crate) fn retire(&mut self, shares: u64, supply: u64) -> Result<u64> {
+        let lamports = self.shares_to_lamports(shares, supply)?;
+        require!(lamports > 0, ErrorCode::InvalidAmount);
+        self.total_assets = self
+            .total_assets
+            .checked_sub(lamports)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(lamports)
+    }
+
+    /// Shares minted for `lamports`, rounded down in the pool's favour
+    pub(crate) fn lamports_to_shares(&self, lamports: u64, supply: u64) -> Result<u64> {
+        if supply == 0 || self.total_assets == 0 {
+            return Ok(lamports);
+        }
+        let shares = (lamports as u128)
+            .checked_mul(supply as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / self.total_assets as u128;
+        u64::try_from(shares).map_err(|_| error!(ErrorCode::Overflow))
+    }
+
+    /// Lamports `shares` redeem for, rounded down in the pool's favour
+    pub(crate) fn shares_to_lamports(&self, shares: u64, supply: u64) -> Result<u64> {
+        if supply == 0 {
+            return Ok(0);
+        }
+        let lamports = (shares as u128)
+            .checked_mul(self.total_assets as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / supply as u128;
+        u64::try_from(lamports).map_err(|_| error!(ErrorCode::Overflow))
+    }
+}
+
+/// FIFO queue of pooled withdrawals; tickets are paid strictly in id order
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalQueue {
+    /// Id of the next ticket to be paid
+    pub head: u64,
+    /// Id the next requested ticket will get
+    pub tail: u64,
+    pub notice_seconds: i64,
+    /// Unlock time of the newest ticket; later tickets never unlock before it, so a
+    /// shorter notice can't leave them waiting behind a longer one
+    pub last_unlock_ts: i64,
+    pub bump: u8,
+}
+
+impl WithdrawalQueue {
+    /// Fill in `ticket` as the next in line
+    pub(crate) fn enqueue(
+        &mut self,
+        ticket: &mut WithdrawalTicket,
+        owner: Pubkey,
+        amount: u64,
+        pooled: bool,
+        bump: u8,
+        now: i64,
+    ) -> Result<()> {
+        let unlock_ts = now
+            .checked_add(self.notice_seconds)
+            .ok_or(ErrorCode::Overflow)?
+            .max(self.last_unlock_ts);
+        ticket.id = self.tail;
+        ticket.owner = owner;
+        ticket.amount = amount;
+        ticket.unlock_ts = unlock_ts;
+        ticket.pooled = pooled;
+        ticket.bump = bump;
+        self.last_unlock_ts = unlock_ts;
+        self.tail = self.tail.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+        emit!(WithdrawalRequested {
+            owner,
+            ticket_id: ticket.id,
+            amount,
+            unlock_ts,
+        });
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalTicket {
+    pub id: u64,
+    pub owner: Pubkey,
+    /// Lamports owed, fixed at request time (0 once cancelled)
+    pub amount: u64,
+    pub unlock_ts: i64,
+    /// Requested from the owner's pooled position rather than minted share tokens
+    pub pooled: bool,
+    pub bump: u8,
+}
+
+/// Negotiated fee rate for one user or one integrating partner program
+#[account]
+#[derive(InitSpace)]
+pub struct FeeOverride {
+    /// User wallet or partner program id
+    pub subject: Pubkey,
+    pub fee_basis_points: u16,
+    pub bump: u8,
+}
+
+/// Per-mint settings for a user's token vault
+#[account]
+#[derive(InitSpace)]
+pub struct TokenVaultConfig {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Transfers are rounded up to a multiple of this, in the mint's base units (0 = off)
+    pub round_up_unit: u64,
+    pub bump: u8,
+}
+
+// Events
+#[event]
+pub struct WithdrawalRequested {
+    pub owner: Pubkey,
+    pub ticket_id: u64,
+    pub amount: u64,
+    pub unlock_ts: i64,
+}
+
+#[event]
+pub struct WithdrawalCancelled {
+    pub owner: Pubkey,
+    pub ticket_id: u64,
+    pub amount: u64,
+    /// Shares returned to the owner
+    pub shares: u64,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalClaimed {
+    pub owner: Pubkey,
+    pub ticket_id: u64,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct UserFrozen {
+    pub owner: Pubkey,
+    pub recovery_destination: Pubkey,
+    pub frozen_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserUnfrozen {
+    pub owner: Pubkey,
+    pub unfrozen_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FrozenWithdrawal {
+    pub owner: Pubkey,
+    pub recovery_destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FrozenTokenWithdrawal {
+    pub owner: Pubkey,
+    pub recovery_destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositedFor {
+    pub payer: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub memo: Option<String>,
+}
@@ -0,0 +1,930 @@
+//! Token savings from hooked Token-2022 transfers and achievement NFTs
+
+use crate::*;
+
+pub(crate) fn recover_token_vault<'info>(
+    ctx: Context<'_, '_, '_, 'info, RecoverTokenVault<'info>>,
+) -> Result<()> {
+    let new_owner = ctx.accounts.new_owner.key();
+    ctx.accounts
+        .old_user_config
+        .require_recovered_to(&new_owner)?;
+
+    let old_owner = ctx.accounts.old_owner.key();
+    let mint_key = ctx.accounts.mint.key();
+    let seeds: &[&[u8]] = &[
+        b"token_vault".as_ref(),
+        old_owner.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.old_token_vault],
+    ];
+    let signer_seeds = &[seeds];
+
+    let amount = ctx.accounts.old_token_vault.amount;
+    if amount > 0 {
+        invoke_transfer_checked(
+            ctx.accounts.token_program.key,
+            ctx.accounts.old_token_vault.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.new_token_vault.to_account_info(),
+            ctx.accounts.old_token_vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.mint.decimals,
+            signer_seeds,
+        )?;
+    }
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.old_token_vault.to_account_info(),
+            destination: ctx.accounts.new_owner.to_account_info(),
+            authority: ctx.accounts.old_token_vault.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    log_msg!(
+        "Token vault for mint {} recovered to {} ({} tokens)",
+        mint_key,
+        new_owner,
+        amount
+    );
+    Ok(())
+}
+
+pub(crate) fn set_round_up_unit(ctx: Context<SetRoundUpUnit>, round_up_unit: u64) -> Result<()> {
+    let token_vault_config = &mut ctx.accounts.token_vault_config;
+    token_vault_config.owner = ctx.accounts.user.key();
+    token_vault_config.mint = ctx.accounts.mint.key();
+    token_vault_config.round_up_unit = round_up_unit;
+    token_vault_config.bump = ctx.bumps.token_vault_config;
+    ctx.accounts
+        .user_config
+        .record_activity(Clock::get()?.unix_timestamp);
+
+    log_msg!(
+        "Round-up unit for mint {} set to {}",
+        token_vault_config.mint,
+        round_up_unit
+    );
+    Ok(())
+}
+
+pub(crate) fn process_token_transfer<'info>(
+    ctx: Context<'_, '_, '_, 'info, ProcessTokenTransfer<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let treasury_config = &ctx.accounts.treasury_config;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    ctx.accounts.user_config.require_active()?;
+    let unit = ctx.accounts.token_vault_config.round_up_unit;
+    let round_up = if unit == 0 {
+        0
+    } else {
+        (unit - amount % unit) % unit
+    };
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let platform_fee = calculate_fee(round_up, fee_basis_points)?;
+    let saved = round_up
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let decimals = ctx.accounts.mint.decimals;
+    let token_transfer = |to: AccountInfo<'info>, value: u64| -> Result<()> {
+        if value == 0 {
+            return Ok(());
+        }
+        // Resolves transfer-hook extra accounts from remaining_accounts for hooked mints
+        invoke_transfer_checked(
+            ctx.accounts.token_program.key,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            to,
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            value,
+            decimals,
+            &[],
+        )
+        .map_err(Into::into)
+    };
+
+    // The payment itself, then the round-up split between vault and treasury
+    token_transfer(
+        ctx.accounts.destination_token_account.to_account_info(),
+        amount,
+    )?;
+    token_transfer(ctx.accounts.token_vault.to_account_info(), saved)?;
+    token_transfer(
+        ctx.accounts.treasury_token_account.to_account_info(),
+        platform_fee,
+    )?;
+
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!(
+        "Token transfer of {} saved {} (fee: {})",
+        amount,
+        saved,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn settle_hook_savings<'info>(
+    ctx: Context<'_, '_, '_, 'info, SettleHookSavings<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        !ctx.accounts
+            .treasury_config
+            .is_paused(PAUSE_DEPOSITS, Clock::get()?.unix_timestamp),
+        ErrorCode::ProtocolPaused
+    );
+    ctx.accounts.user_config.require_active()?;
+
+    let owner_key = ctx.accounts.user_config.owner;
+    let mint_key = ctx.accounts.mint.key();
+    let seeds: &[&[u8]] = &[
+        b"token_vault".as_ref(),
+        owner_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.token_vault],
+    ];
+    let signer_seeds = &[seeds];
+
+    // savings_hook::record_settlement(amount), signed by the token vault
+    let mut data = hash(b"global:record_settlement").to_bytes()[..8].to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    invoke_signed(
+        &Instruction {
+            program_id: savings_hook_program::ID,
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.pending_savings.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_vault.key(), true),
+            ],
+            data,
+        },
+        &[
+            ctx.accounts.pending_savings.to_account_info(),
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.savings_hook_program.to_account_info(),
+        ],
+        signer_seeds,
+    )?;
+
+    invoke_transfer_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.owner_token_account.to_account_info(),
+        ctx.accounts.mint.to_account_info(),
+        ctx.accounts.token_vault.to_account_info(),
+        ctx.accounts.token_vault.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+        ctx.accounts.mint.decimals,
+        signer_seeds,
+    )?;
+
+    log_msg!(
+        "Settled {} hook savings of mint {} into the token vault",
+        amount,
+        mint_key
+    );
+    Ok(())
+}
+
+pub(crate) fn withdraw_token_savings<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawTokenSavings<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let treasury_config = &ctx.accounts.treasury_config;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    ctx.accounts.user_config.require_active()?;
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let total_needed = amount
+        .checked_add(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        ctx.accounts.token_vault.amount >= total_needed,
+        ErrorCode::InsufficientFunds
+    );
+
+    let user_key = ctx.accounts.user.key();
+    let mint_key = ctx.accounts.mint.key();
+    let seeds: &[&[u8]] = &[
+        b"token_vault".as_ref(),
+        user_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.token_vault],
+    ];
+    let signer_seeds = &[seeds];
+    let decimals = ctx.accounts.mint.decimals;
+    let vault_transfer = |to: AccountInfo<'info>, value: u64| -> Result<()> {
+        if value == 0 {
+            return Ok(());
+        }
+        invoke_transfer_checked(
+            ctx.accounts.token_program.key,
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            to,
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.remaining_accounts,
+            value,
+            decimals,
+            signer_seeds,
+        )
+        .map_err(Into::into)
+    };
+
+    vault_transfer(ctx.accounts.user_token_account.to_account_info(), amount)?;
+    vault_transfer(
+        ctx.accounts.treasury_token_account.to_account_info(),
+        platform_fee,
+    )?;
+
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!(
+        "Withdrawn {} tokens from token vault (fee: {})",
+        amount,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn set_savings_rate(ctx: Context<SetSavingsRate>, savings_rate: u8) -> Result<()> {
+    require!(savings_rate <= 90, ErrorCode::InvalidSavingsRate);
+
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.savings_rate = savings_rate;
+    user_config.record_activity(Clock::get()?.unix_timestamp);
+
+    log_msg!("Savings rate set to {}%", savings_rate);
+    Ok(())
+}
+
+pub(crate) fn initialize_token_vault(ctx: Context<InitializeTokenVault>) -> Result<()> {
+    ctx.accounts
+        .user_config
+        .record_activity(Clock::get()?.unix_timestamp);
+    log_msg!(
+        "Token vault for mint {} initialized",
+        ctx.accounts.mint.key()
+    );
+    Ok(())
+}
+
+pub(crate) fn withdraw_frozen_tokens<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawFrozenTokens<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let user_config = &ctx.accounts.user_config;
+    user_config
+        .require_frozen_withdrawal_authority(&ctx.accounts.roles, ctx.accounts.authority.key)?;
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let fee_basis_points =
+        fee_basis_points_for(treasury_config, &ctx.accounts.fee_rate, &user_config.owner)?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let total_needed = amount
+        .checked_add(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        ctx.accounts.token_vault.amount >= total_needed,
+        ErrorCode::InsufficientFunds
+    );
+
+    let owner_key = user_config.owner;
+    let mint_key = ctx.accounts.mint.key();
+    let seeds: &[&[u8]] = &[
+        b"token_vault".as_ref(),
+        owner_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.token_vault],
+    ];
+    let signer_seeds = &[seeds];
+    let decimals = ctx.accounts.mint.decimals;
+    let vault_transfer = |to: AccountInfo<'info>, value: u64| -> Result<()> {
+        if value == 0 {
+            return Ok(());
+        }
+        invoke_transfer_checked(
+            ctx.accounts.token_program.key,
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            to,
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.remaining_accounts,
+            value,
+            decimals,
+            signer_seeds,
+        )
+        .map_err(Into::into)
+    };
+
+    vault_transfer(
+        ctx.accounts.recovery_token_account.to_account_info(),
+        amount,
+    )?;
+    vault_transfer(
+        ctx.accounts.treasury_token_account.to_account_info(),
+        platform_fee,
+    )?;
+
+    emit!(FrozenTokenWithdrawal {
+        owner: owner_key,
+        recovery_destination: user_config.recovery_destination,
+        mint: mint_key,
+        amount,
+        timestamp: now,
+    });
+    log_msg!(
+        "Frozen token vault withdrew {} tokens to recovery destination (fee: {})",
+        amount,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn claim_achievement(
+    ctx: Context<ClaimAchievement>,
+    milestone: Milestone,
+) -> Result<()> {
+    let uri_base = &ctx.accounts.treasury_config.achievement_uri_base;
+    require!(!uri_base.is_empty(), ErrorCode::AchievementsNotConfigured);
+
+    // Pooled positions count through `net_deposited` just like the vault
+    let user_config = &ctx.accounts.user_config;
+    let saved = user_config.net_deposited;
+    let reached = match milestone {
+        Milestone::FirstDeposit => user_config.best_streak > 0,
+        Milestone::OneSolSaved => saved >= LAMPORTS_PER_SOL,
+        Milestone::ThirtyDayStreak => user_config.best_streak >= ACHIEVEMENT_STREAK_PERIODS,
+        Milestone::GoalReached => user_config.savings_goal > 0 && saved >= user_config.savings_goal,
+    };
+    require!(reached, ErrorCode::MilestoneNotReached);
+    let uri = format!("{}{}.json", uri_base, milestone.slug());
+
+    let user_key = ctx.accounts.user.key();
+    let milestone_seed = [milestone as u8];
+    let seeds: &[&[u8]] = &[
+        b"achievement_mint".as_ref(),
+        user_key.as_ref(),
+        &milestone_seed,
+        &[ctx.bumps.achievement_mint],
+    ];
+    mint_achievement_nft(ctx.accounts, milestone.name(), uri, &[seeds])?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let achievement = &mut ctx.accounts.achievement;
+    achievement.owner = user_key;
+    achievement.milestone = milestone;
+    achievement.mint = ctx.accounts.achievement_mint.key();
+    achievement.claimed_at = now;
+    achievement.bump = ctx.bumps.achievement;
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!("Achievement '{}' minted to {}", milestone.name(), user_key);
+    Ok(())
+}
+
+/// Create the achievement mint (non-transferable, metadata stored on the mint itself),
+/// mint the single token to the user's ATA and then drop the mint authority
+pub(crate) fn mint_achievement_nft(
+    accounts: &ClaimAchievement,
+    name: &str,
+    uri: String,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let token_program_id = accounts.token_program.key();
+    let mint_info = accounts.achievement_mint.to_account_info();
+    let mint_key = mint_info.key();
+
+    // Token-2022 reallocs the mint for the metadata TLV, so fund that up front
+    let metadata = TokenMetadata {
+        mint: mint_key,
+        name: name.to_string(),
+        symbol: ACHIEVEMENT_SYMBOL.to_string(),
+        uri,
+        ..Default::default()
+    };
+    let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::MetadataPointer,
+    ])?;
+    let lamports = Rent::get()?.minimum_balance(mint_len + metadata.tlv_size_of()?);
+    create_account(
+        CpiContext::new_with_signer(
+            accounts.system_program.to_account_info(),
+            CreateAccount {
+                from: accounts.user.to_account_info(),
+                to: mint_info.clone(),
+            },
+            signer_seeds,
+        ),
+        lamports,
+        mint_len as u64,
+        &token_program_id,
+    )?;
+
+    invoke(
+        &initialize_non_transferable_mint(&token_program_id, &mint_key)?,
+        std::slice::from_ref(&mint_info),
+    )?;
+    invoke(
+        &metadata_pointer::instruction::initialize(
+            &token_program_id,
+            &mint_key,
+            Some(mint_key),
+            Some(mint_key),
+        )?,
+        std::slice::from_ref(&mint_info),
+    )?;
+    invoke(
+        &initialize_mint2(&token_program_id, &mint_key, &mint_key, None, 0)?,
+        std::slice::from_ref(&mint_info),
+    )?;
+    invoke_signed(
+        &spl_token_metadata_interface::instruction::initialize(
+            &token_program_id,
+            &mint_key,
+            &mint_key,
+            &mint_key,
+            &mint_key,
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        ),
+        std::slice::from_ref(&mint_info),
+        signer_seeds,
+    )?;
+
+    associated_token::create(CpiContext::new(
+        accounts.associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: accounts.user.to_account_info(),
+            associated_token: accounts.user_token_account.to_account_info(),
+            authority: accounts.user.to_account_info(),
+            mint: mint_info.clone(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+        },
+    ))?;
+    invoke_signed(
+        &mint_to(
+            &token_program_id,
+            &mint_key,
+            accounts.user_token_account.key,
+            &mint_key,
+            &[],
+            1,
+        )?,
+        &[
+            mint_info.clone(),
+            accounts.user_token_account.to_account_info(),
+        ],
+        signer_seeds,
+    )?;
+
+    // Fixed supply of one
+    invoke_signed(
+        &set_authority(
+            &token_program_id,
+            &mint_key,
+            None,
+            AuthorityType::MintTokens,
+            &mint_key,
+            &[],
+        )?,
+        &[mint_info],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+// Account Structures
+#[derive(Accounts)]
+pub struct RecoverTokenVault<'info> {
+    /// CHECK: Bound to old_user_config and old_token_vault through their seeds
+    pub old_owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"user_config", old_owner.key().as_ref()],
+        bump = old_user_config.bump
+    )]
+    pub old_user_config: Account<'info, UserConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", old_owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = old_token_vault,
+        token::token_program = token_program
+    )]
+    pub old_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = new_owner,
+        seeds = [b"token_vault", new_owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = new_token_vault,
+        token::token_program = token_program
+    )]
+    pub new_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoundUpUnit<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + TokenVaultConfig::INIT_SPACE,
+        seeds = [b"token_vault_config", user.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub token_vault_config: Account<'info, TokenVaultConfig>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessTokenTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = user,
+        token::token_program = token_program
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"token_vault_config", user.key().as_ref(), mint.key().as_ref()],
+        bump = token_vault_config.bump
+    )]
+    pub token_vault_config: Account<'info, TokenVaultConfig>,
+
+    /// User's per-mint savings account; owns itself so only this program can move funds
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"token_vault", user.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_vault,
+        token::token_program = token_program
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA, authority of the treasury token account
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"treasury_token", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenSavings<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", user.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_vault,
+        token::token_program = token_program
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_token", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleHookSavings<'info> {
+    #[account(
+        seeds = [b"user_config", user_config.owner.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = user_config.owner,
+        token::token_program = token_program
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", user_config.owner.as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_vault,
+        token::token_program = token_program
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_savings", user_config.owner.as_ref(), mint.key().as_ref()],
+        seeds::program = savings_hook_program::ID,
+        bump
+    )]
+    /// CHECK: Owner's PendingSavings, validated and updated by the hook
+    pub pending_savings: UncheckedAccount<'info>,
+
+    #[account(address = savings_hook_program::ID)]
+    /// CHECK: Savings-hook program
+    pub savings_hook_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetSavingsRate<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"token_vault", user.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_vault,
+        token::token_program = token_program
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFrozenTokens<'info> {
+    #[account(
+        seeds = [b"user_config", user_config.owner.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", user_config.owner.as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_vault,
+        token::token_program = token_program
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = user_config.recovery_destination,
+        token::token_program = token_program
+    )]
+    pub recovery_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA, authority of the treasury token account
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"treasury_token", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    /// Owner or risk manager
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestone: Milestone)]
+pub struct ClaimAchievement<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Achievement::INIT_SPACE,
+        seeds = [b"achievement", user.key().as_ref(), [milestone as u8].as_ref()],
+        bump
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    #[account(
+        mut,
+        seeds = [b"achievement_mint", user.key().as_ref(), [milestone as u8].as_ref()],
+        bump
+    )]
+    /// CHECK: Created and initialized as a Token-2022 mint by the instruction
+    pub achievement_mint: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: User's associated token account for the achievement mint; the ATA program checks the address
+    pub user_token_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,3004 @@
+//! Personal SOL vaults and what builds on them: recovery, inheritance, allowances,
+//! joint vaults, savings circles and sponsor matching
+
+use crate::*;
+
+pub(crate) fn initialize_user(
+    ctx: Context<InitializeUser>,
+    allowlist_proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let treasury_config = &ctx.accounts.treasury_config;
+    require!(
+        !treasury_config.is_paused(PAUSE_NEW_USERS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    if treasury_config.onboarding_verifier.is_some() || treasury_config.allowlist_root.is_some() {
+        let verified = match (&ctx.accounts.verifier, treasury_config.onboarding_verifier) {
+            (Some(verifier), Some(expected)) => verifier.key() == expected,
+            _ => false,
+        };
+        let allowlisted = treasury_config.allowlist_root.is_some_and(|root| {
+            verify_allowlist_proof(&ctx.accounts.user.key(), &allowlist_proof, root)
+        });
+        require!(verified || allowlisted, ErrorCode::NotAllowlisted);
+    }
+
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.owner = ctx.accounts.user.key();
+    user_config.bump = ctx.bumps.user_config;
+    user_config.vault_bump = ctx.bumps.vault;
+    user_config.version = USER_CONFIG_VERSION;
+    user_config.beneficiary = Pubkey::default();
+    user_config.inactivity_period = 0;
+    user_config.last_activity_ts = now;
+    user_config.savings_rate = 0;
+    user_config.max_vault_balance_override = None;
+    user_config.max_daily_deposit_override = None;
+    user_config.deposit_window_ts = 0;
+    user_config.deposited_in_window = 0;
+    user_config.status = UserStatus::Active;
+    user_config.recovery_destination = Pubkey::default();
+    user_config.streak = 0;
+    user_config.best_streak = 0;
+    user_config.last_deposit_period = 0;
+    user_config.reward_points = 0;
+    user_config.fee_credit = 0;
+    user_config.savings_goal = 0;
+    user_config.reward_balance = 0;
+    user_config.reward_debt = 0;
+    user_config.pending_rewards = 0;
+    user_config.pooled_shares = 0;
+    user_config.net_deposited = 0;
+
+    log_msg!("User initialized");
+    Ok(())
+}
+
+pub(crate) fn migrate_user_config(ctx: Context<MigrateUserConfig>) -> Result<()> {
+    let info = ctx.accounts.user_config.to_account_info();
+    grow_account(
+        &info,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        8 + UserConfig::INIT_SPACE,
+    )?;
+
+    let mut user_config = UserConfig::try_deserialize(&mut &info.data.borrow()[..])?;
+    require!(
+        user_config.version < USER_CONFIG_VERSION,
+        ErrorCode::AlreadyMigrated
+    );
+    if user_config.version == 0 {
+        user_config.beneficiary = Pubkey::default();
+        user_config.inactivity_period = 0;
+        user_config.last_activity_ts = Clock::get()?.unix_timestamp;
+        user_config.savings_rate = 0;
+        user_config.max_vault_balance_override = None;
+        user_config.max_daily_deposit_override = None;
+        user_config.deposit_window_ts = 0;
+        user_config.deposited_in_window = 0;
+        user_config.status = UserStatus::Active;
+        user_config.recovery_destination = Pubkey::default();
+        user_config.streak = 0;
+        user_config.best_streak = 0;
+        user_config.last_deposit_period = 0;
+        user_config.reward_points = 0;
+        user_config.fee_credit = 0;
+        user_config.savings_goal = 0;
+        user_config.reward_balance = 0;
+        user_config.reward_debt = 0;
+        user_config.pending_rewards = 0;
+        user_config.pooled_shares = 0;
+
+        // Launch-era deposits were only counted in `TreasuryConfig::total_tvl`; move
+        // this vault's balance, and the matching share of the cap, into its shard
+        let balance = ctx.accounts.vault.lamports();
+        let treasury_config = &mut ctx.accounts.treasury_config;
+        let seeded = balance.min(treasury_config.unmigrated_tvl);
+        treasury_config.unmigrated_tvl -= seeded;
+        let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+        stats_shard.add_tvl(balance)?;
+        stats_shard.add_quota(seeded)?;
+        // Start earning rewards on the balance it already holds
+        user_config.net_deposited = balance;
+        user_config.sync_rewards(&mut stats_shard)?;
+    }
+    let from_version = user_config.version;
+    user_config.version = USER_CONFIG_VERSION;
+    user_config.try_serialize(&mut &mut info.data.borrow_mut()[..])?;
+
+    log_msg!(
+        "UserConfig for {} migrated from v{} to v{}",
+        user_config.owner,
+        from_version,
+        USER_CONFIG_VERSION
+    );
+    Ok(())
+}
+
+pub(crate) fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    // Calculate platform fee at any negotiated rate, less fee credit redeemed from points
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let platform_fee = ctx
+        .accounts
+        .user_config
+        .apply_fee_credit(calculate_fee(amount, fee_basis_points)?);
+
+    let amount_after_fee = amount
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    // Optional sponsor match, paid from the matching program's funds into the vault.
+    // It lands first so the vault balance cap below counts it too.
+    let matched = match (
+        ctx.accounts.matching_program.as_mut(),
+        ctx.accounts.matching_funds.as_ref(),
+        ctx.accounts.matching_participant.as_mut(),
+    ) {
+        (Some(matching_program), Some(matching_funds), Some(matching_participant)) => pay_match(
+            matching_program,
+            matching_funds,
+            matching_participant,
+            ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.user.key(),
+            amount_after_fee,
+            now,
+        )?,
+        (None, None, None) => 0,
+        _ => return err!(ErrorCode::InvalidMatchingProgram),
+    };
+
+    // Per-user caps so no single depositor can take the whole TVL cap
+    ctx.accounts.user_config.record_deposit(
+        treasury_config,
+        ctx.accounts.vault.lamports(),
+        amount,
+        amount_after_fee,
+        now,
+    )?;
+    // Only deposits the owner makes themselves count toward streaks
+    ctx.accounts
+        .user_config
+        .record_streak(amount_after_fee, now)?;
+    ctx.accounts.user_config.record_saved(amount_after_fee)?;
+
+    // Fee and principal stay separate transfers: they go to different accounts, and
+    // vault and treasury are system-owned PDAs, so one can't be funded through the
+    // other without a further CPI. batch_deposit, whose fee is shared, pays it once.
+
+    // Transfer fee to treasury
+    if platform_fee > 0 {
+        let fee_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        transfer(fee_transfer, platform_fee)?;
+    }
+
+    // Transfer remaining SOL to vault
+    let vault_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+        },
+    );
+    transfer(vault_transfer, amount_after_fee)?;
+
+    // Everything that landed in the vault, match included, counts against the cap
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.add_deposit(
+        amount_after_fee
+            .checked_add(matched)
+            .ok_or(ErrorCode::Overflow)?,
+    )?;
+    ctx.accounts.user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!(
+        "Deposited {} lamports to vault (fee: {} lamports)",
+        amount_after_fee,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.require_active()?;
+    let treasury_config = &ctx.accounts.treasury_config;
+
+    // Withdrawal pauses lapse on their own after MAX_WITHDRAWAL_PAUSE_SECONDS
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    // Calculate platform fee at any negotiated rate, less fee credit redeemed from points
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user.key(),
+    )?;
+    let platform_fee = user_config.apply_fee_credit(calculate_fee(amount, fee_basis_points)?);
+
+    let total_needed = amount
+        .checked_add(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    // Check vault has sufficient balance
+    let vault_balance = ctx.accounts.vault.lamports();
+    require!(vault_balance >= total_needed, ErrorCode::InsufficientFunds);
+
+    // Signer seeds for the vault PDA
+    let user_key = ctx.accounts.user.key();
+    let seeds: &[&[u8]] = &[
+        b"vault".as_ref(),
+        user_key.as_ref(),
+        &[user_config.vault_bump],
+    ];
+
+    // Transfer from vault to user, then the fee to treasury
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        amount,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.sub_tvl(total_needed)?;
+    ctx.accounts.user_config.record_withdrawn(total_needed);
+    ctx.accounts.user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!(
+        "Withdrawn {} lamports from vault (fee: {} lamports)",
+        amount,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn set_recovery_guardians(
+    ctx: Context<SetRecoveryGuardians>,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    delay_seconds: i64,
+) -> Result<()> {
+    let owner = ctx.accounts.user.key();
+    require!(
+        !guardians.is_empty() && guardians.len() <= MAX_RECOVERY_GUARDIANS,
+        ErrorCode::InvalidGuardians
+    );
+    require!(
+        threshold > 0 && threshold as usize <= guardians.len(),
+        ErrorCode::InvalidGuardians
+    );
+    require!(
+        delay_seconds >= MIN_RECOVERY_DELAY_SECONDS,
+        ErrorCode::InvalidGuardians
+    );
+    for (i, guardian) in guardians.iter().enumerate() {
+        require!(
+            *guardian != owner && !guardians[..i].contains(guardian),
+            ErrorCode::InvalidGuardians
+        );
+    }
+
+    let recovery_config = &mut ctx.accounts.recovery_config;
+    recovery_config.owner = owner;
+    recovery_config.guardians = guardians;
+    recovery_config.threshold = threshold;
+    recovery_config.delay_seconds = delay_seconds;
+    recovery_config.bump = ctx.bumps.recovery_config;
+    recovery_config.clear_pending();
+
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!(
+        "Recovery guardians set: {}-of-{}, {}s delay",
+        threshold,
+        recovery_config.guardians.len(),
+        delay_seconds
+    );
+    Ok(())
+}
+
+pub(crate) fn initiate_recovery(
+    ctx: Context<GuardianRecoveryAction>,
+    new_owner: Pubkey,
+) -> Result<()> {
+    let recovery_config = &mut ctx.accounts.recovery_config;
+    let guardian = ctx.accounts.guardian.key();
+
+    require!(
+        recovery_config.guardians.contains(&guardian),
+        ErrorCode::Unauthorized
+    );
+    require!(
+        recovery_config.pending_owner == Pubkey::default(),
+        ErrorCode::RecoveryInProgress
+    );
+    require!(
+        new_owner != Pubkey::default() && new_owner != recovery_config.owner,
+        ErrorCode::InvalidRecoveryOwner
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    recovery_config.pending_owner = new_owner;
+    recovery_config.approvals = vec![guardian];
+    recovery_config.initiated_at = now;
+    if recovery_config.approvals.len() >= recovery_config.threshold as usize {
+        recovery_config.approved_at = now;
+    }
+
+    log_msg!(
+        "Recovery of {} to {} initiated",
+        recovery_config.owner,
+        new_owner
+    );
+    Ok(())
+}
+
+pub(crate) fn approve_recovery(
+    ctx: Context<GuardianRecoveryAction>,
+    new_owner: Pubkey,
+) -> Result<()> {
+    let recovery_config = &mut ctx.accounts.recovery_config;
+    let guardian = ctx.accounts.guardian.key();
+
+    require!(
+        recovery_config.guardians.contains(&guardian),
+        ErrorCode::Unauthorized
+    );
+    require!(
+        recovery_config.pending_owner != Pubkey::default(),
+        ErrorCode::NoRecoveryPending
+    );
+    require!(
+        recovery_config.pending_owner == new_owner,
+        ErrorCode::InvalidRecoveryOwner
+    );
+    require!(
+        !recovery_config.approvals.contains(&guardian),
+        ErrorCode::AlreadyApproved
+    );
+
+    recovery_config.approvals.push(guardian);
+    if recovery_config.approved_at == 0
+        && recovery_config.approvals.len() >= recovery_config.threshold as usize
+    {
+        recovery_config.approved_at = Clock::get()?.unix_timestamp;
+    }
+
+    log_msg!(
+        "Recovery approved ({}/{})",
+        recovery_config.approvals.len(),
+        recovery_config.threshold
+    );
+    Ok(())
+}
+
+pub(crate) fn cancel_recovery(ctx: Context<CancelRecovery>) -> Result<()> {
+    let recovery_config = &mut ctx.accounts.recovery_config;
+    require!(
+        recovery_config.pending_owner != Pubkey::default(),
+        ErrorCode::NoRecoveryPending
+    );
+    recovery_config.clear_pending();
+
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!("Recovery cancelled by owner");
+    Ok(())
+}
+
+pub(crate) fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+    let recovery_config = &ctx.accounts.recovery_config;
+    require!(
+        recovery_config.approved_at != 0
+            && recovery_config.approvals.len() >= recovery_config.threshold as usize,
+        ErrorCode::ThresholdNotMet
+    );
+    let ready_at = recovery_config
+        .approved_at
+        .checked_add(recovery_config.delay_seconds)
+        .ok_or(ErrorCode::Overflow)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= ready_at, ErrorCode::TimelockNotElapsed);
+
+    // Move every lamport from the old vault into the new owner's vault
+    let old_owner = ctx.accounts.old_owner.key();
+    let old_user_config = &ctx.accounts.old_user_config;
+    old_user_config.require_active()?;
+    let balance = ctx.accounts.old_vault.lamports();
+    transfer_from_pda(
+        &ctx.accounts.old_vault,
+        ctx.accounts.new_vault.to_account_info(),
+        &ctx.accounts.system_program,
+        &[
+            b"vault".as_ref(),
+            old_owner.as_ref(),
+            &[old_user_config.vault_bump],
+        ],
+        balance,
+    )?;
+
+    // Carry the savings config over; the recovery PDA is closed
+    let mut migrated = (**old_user_config).clone();
+    migrated.owner = ctx.accounts.new_owner.key();
+    migrated.bump = ctx.bumps.new_user_config;
+    migrated.vault_bump = ctx.bumps.new_vault;
+    migrated.record_activity(now);
+
+    // Rewards accrued so far carry over; the stake leaves the old shard
+    let mut old_stats_shard = ctx.accounts.old_stats_shard.load_mut()?;
+    migrated.stake_rewards(&mut old_stats_shard, 0)?;
+
+    // The new owner may hash to a different stats shard; its quota moves along
+    if ctx.accounts.old_stats_shard.key() != ctx.accounts.new_stats_shard.key() {
+        old_stats_shard.total_tvl = old_stats_shard.total_tvl.saturating_sub(balance);
+        let quota = old_stats_shard.release_quota(balance);
+        drop(old_stats_shard);
+        let mut new_stats_shard = ctx.accounts.new_stats_shard.load_mut()?;
+        new_stats_shard.add_tvl(balance)?;
+        new_stats_shard.add_quota(quota)?;
+    } else {
+        drop(old_stats_shard);
+    }
+
+    let mut new_stats_shard = ctx.accounts.new_stats_shard.load_mut()?;
+    migrated.sync_rewards(&mut new_stats_shard)?;
+    drop(new_stats_shard);
+    ctx.accounts.new_user_config.set_inner(migrated);
+
+    // Keep the old config as a record of where the vault went, so the new owner
+    // can move what is still keyed by the old owner (see `recover_token_vault`,
+    // `recover_withdrawal_ticket` and `revoke_recovered_allowance`)
+    let old_user_config = &mut ctx.accounts.old_user_config;
+    old_user_config.status = UserStatus::Recovered;
+    old_user_config.recovery_destination = ctx.accounts.new_owner.key();
+    old_user_config.beneficiary = Pubkey::default();
+    old_user_config.inactivity_period = 0;
+    old_user_config.reward_balance = 0;
+    old_user_config.reward_debt = 0;
+    old_user_config.pending_rewards = 0;
+    old_user_config.pooled_shares = 0;
+    old_user_config.net_deposited = 0;
+
+    log_msg!(
+        "Vault recovered from {} to {} ({} lamports)",
+        old_owner,
+        ctx.accounts.new_owner.key(),
+        balance
+    );
+    Ok(())
+}
+
+pub(crate) fn revoke_recovered_allowance(ctx: Context<RevokeRecoveredAllowance>) -> Result<()> {
+    ctx.accounts
+        .old_user_config
+        .require_recovered_to(&ctx.accounts.new_owner.key())?;
+
+    log_msg!(
+        "Allowance for {} on recovered vault revoked",
+        ctx.accounts.allowance.spender
+    );
+    Ok(())
+}
+
+pub(crate) fn set_beneficiary(
+    ctx: Context<SetBeneficiary>,
+    beneficiary: Pubkey,
+    inactivity_period: i64,
+) -> Result<()> {
+    let user_config = &mut ctx.accounts.user_config;
+    if beneficiary == Pubkey::default() {
+        user_config.inactivity_period = 0;
+    } else {
+        require!(
+            beneficiary != user_config.owner,
+            ErrorCode::InvalidBeneficiary
+        );
+        require!(
+            inactivity_period >= MIN_INACTIVITY_PERIOD_SECONDS,
+            ErrorCode::InvalidBeneficiary
+        );
+        user_config.inactivity_period = inactivity_period;
+    }
+    user_config.beneficiary = beneficiary;
+
+    let now = Clock::get()?.unix_timestamp;
+    user_config.record_activity(now);
+
+    log_msg!(
+        "Beneficiary set to {} after {}s of inactivity",
+        beneficiary,
+        user_config.inactivity_period
+    );
+    Ok(())
+}
+
+pub(crate) fn claim_inheritance(ctx: Context<ClaimInheritance>) -> Result<()> {
+    let user_config = &ctx.accounts.user_config;
+    let treasury_config = &ctx.accounts.treasury_config;
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+    user_config.require_active()?;
+    require!(
+        user_config.beneficiary != Pubkey::default(),
+        ErrorCode::InvalidBeneficiary
+    );
+    let claimable_at = user_config
+        .last_activity_ts
+        .checked_add(user_config.inactivity_period)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(now >= claimable_at, ErrorCode::OwnerStillActive);
+
+    let balance = ctx.accounts.vault.lamports();
+    require!(
+        balance > 0 || user_config.pooled_shares > 0,
+        ErrorCode::InsufficientFunds
+    );
+
+    // Same platform fee as a regular withdrawal, taken from the balance, at the
+    // owner's rate
+    let fee_basis_points =
+        fee_basis_points_for(treasury_config, &ctx.accounts.fee_rate, &user_config.owner)?;
+    let platform_fee = calculate_fee(balance, fee_basis_points)?;
+    let payout = balance
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let owner = user_config.owner;
+    let seeds: &[&[u8]] = &[b"vault".as_ref(), owner.as_ref(), &[user_config.vault_bump]];
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.beneficiary.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        payout,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    ctx.accounts.user_config.record_withdrawn(balance);
+
+    // The pooled position passes to the beneficiary too
+    let accounts = &mut *ctx.accounts;
+    let (pooled_payout, pooled_fee) = settle_pooled_position(
+        &mut accounts.user_config,
+        &mut accounts.share_pool,
+        &accounts.share_mint,
+        &accounts.share_vault,
+        accounts.beneficiary.to_account_info(),
+        accounts.treasury.to_account_info(),
+        &accounts.system_program,
+        fee_basis_points,
+    )?;
+
+    // The vault may hold slightly more than it contributed to TVL (rent, rounding)
+    let mut stats_shard = accounts.stats_shard.load_mut()?;
+    stats_shard.total_tvl = stats_shard.total_tvl.saturating_sub(balance);
+    accounts.user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    log_msg!(
+        "Inheritance of {} claimed by {}: {} lamports (fee: {} lamports)",
+        owner,
+        accounts.beneficiary.key(),
+        payout + pooled_payout,
+        platform_fee + pooled_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn grant_allowance(
+    ctx: Context<GrantAllowance>,
+    destination: Pubkey,
+    amount_per_period: u64,
+    period_seconds: i64,
+) -> Result<()> {
+    require!(amount_per_period > 0, ErrorCode::InvalidAmount);
+    require!(period_seconds > 0, ErrorCode::InvalidAllowance);
+
+    let now = Clock::get()?.unix_timestamp;
+    let allowance = &mut ctx.accounts.allowance;
+    allowance.owner = ctx.accounts.user.key();
+    allowance.spender = ctx.accounts.spender.key();
+    allowance.destination = destination;
+    allowance.amount_per_period = amount_per_period;
+    allowance.period_seconds = period_seconds;
+    allowance.period_start = now;
+    allowance.withdrawn_in_period = 0;
+    allowance.bump = ctx.bumps.allowance;
+
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!(
+        "Allowance granted to {}: {} lamports every {}s to {}",
+        allowance.spender,
+        amount_per_period,
+        period_seconds,
+        destination
+    );
+    Ok(())
+}
+
+pub(crate) fn revoke_allowance(ctx: Context<RevokeAllowance>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.user_config.record_activity(now);
+
+    log_msg!("Allowance for {} revoked", ctx.accounts.allowance.spender);
+    Ok(())
+}
+
+pub(crate) fn withdraw_with_allowance(
+    ctx: Context<WithdrawWithAllowance>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let treasury_config = &ctx.accounts.treasury_config;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    ctx.accounts.user_config.require_active()?;
+    let allowance = &mut ctx.accounts.allowance;
+    allowance.roll_period(now)?;
+    let withdrawn = allowance
+        .withdrawn_in_period
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        withdrawn <= allowance.amount_per_period,
+        ErrorCode::AllowanceExceeded
+    );
+    allowance.withdrawn_in_period = withdrawn;
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.user_config.owner,
+    )?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let total_needed = amount
+        .checked_add(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        ctx.accounts.vault.lamports() >= total_needed,
+        ErrorCode::InsufficientFunds
+    );
+
+    let user_config = &ctx.accounts.user_config;
+    let seeds: &[&[u8]] = &[
+        b"vault".as_ref(),
+        user_config.owner.as_ref(),
+        &[user_config.vault_bump],
+    ];
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.destination.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        amount,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.sub_tvl(total_needed)?;
+    ctx.accounts.user_config.record_withdrawn(total_needed);
+    ctx.accounts.user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    log_msg!(
+        "Spender withdrew {} lamports to {} (fee: {} lamports, {}/{} used this period)",
+        amount,
+        allowance.destination,
+        platform_fee,
+        allowance.withdrawn_in_period,
+        allowance.amount_per_period
+    );
+    Ok(())
+}
+
+pub(crate) fn create_joint_vault(
+    ctx: Context<CreateJointVault>,
+    vault_id: u64,
+    members: Vec<Pubkey>,
+    policy: JointWithdrawalPolicy,
+) -> Result<()> {
+    let creator = ctx.accounts.creator.key();
+    require!(
+        members.len() >= 2 && members.len() <= MAX_JOINT_MEMBERS,
+        ErrorCode::InvalidJointVault
+    );
+    require!(members.contains(&creator), ErrorCode::InvalidJointVault);
+    for (i, member) in members.iter().enumerate() {
+        require!(!members[..i].contains(member), ErrorCode::InvalidJointVault);
+    }
+    if let JointWithdrawalPolicy::Threshold { threshold } = policy {
+        require!(
+            threshold > 0 && threshold as usize <= members.len(),
+            ErrorCode::InvalidJointVault
+        );
+    }
+
+    let joint_vault = &mut ctx.accounts.joint_vault;
+    joint_vault.vault_id = vault_id;
+    joint_vault.creator = creator;
+    joint_vault.members = members
+        .into_iter()
+        .map(|key| JointMember {
+            key,
+            contributed: 0,
+            withdrawn: 0,
+        })
+        .collect();
+    joint_vault.policy = policy;
+    joint_vault.proposal_count = 0;
+    joint_vault.bump = ctx.bumps.joint_vault;
+    joint_vault.funds_bump = ctx.bumps.funds;
+
+    log_msg!(
+        "Joint vault {} created with {} members",
+        vault_id,
+        joint_vault.members.len()
+    );
+    Ok(())
+}
+
+pub(crate) fn joint_deposit(ctx: Context<JointDeposit>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let member_index = ctx
+        .accounts
+        .joint_vault
+        .member_index(&ctx.accounts.member.key())?;
+
+    // Joint deposits leave the member's wallet too, so they share its daily limit
+    ctx.accounts
+        .member_config
+        .record_velocity(treasury_config, amount, now)?;
+    ctx.accounts.member_config.record_activity(now);
+
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.member.key(),
+    )?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let amount_after_fee = amount
+        .checked_sub(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    if platform_fee > 0 {
+        let fee_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.member.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        transfer(fee_transfer, platform_fee)?;
+    }
+
+    let funds_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.member.to_account_info(),
+            to: ctx.accounts.funds.to_account_info(),
+        },
+    );
+    transfer(funds_transfer, amount_after_fee)?;
+
+    let member = &mut ctx.accounts.joint_vault.members[member_index];
+    member.contributed = member
+        .contributed
+        .checked_add(amount_after_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    // Joint vaults are tracked in the stats shard their own address hashes to
+    ctx.accounts
+        .stats_shard
+        .load_mut()?
+        .add_deposit(amount_after_fee)?;
+
+    log_msg!(
+        "Deposited {} lamports to joint vault {} (fee: {} lamports)",
+        amount_after_fee,
+        ctx.accounts.joint_vault.vault_id,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn joint_withdraw(ctx: Context<JointWithdraw>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let joint_vault = &ctx.accounts.joint_vault;
+    let member_index = joint_vault.member_index(&ctx.accounts.member.key())?;
+    require!(
+        !matches!(joint_vault.policy, JointWithdrawalPolicy::Threshold { .. }),
+        ErrorCode::ApprovalRequired
+    );
+    let fee_basis_points = fee_basis_points_for(
+        &ctx.accounts.treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.member.key(),
+    )?;
+
+    pay_out_joint_vault(
+        &mut ctx.accounts.joint_vault,
+        Some(member_index),
+        &ctx.accounts.funds,
+        ctx.accounts.member.to_account_info(),
+        &ctx.accounts.treasury_config,
+        fee_basis_points,
+        &ctx.accounts.stats_shard,
+        &ctx.accounts.treasury,
+        &ctx.accounts.system_program,
+        amount,
+    )
+}
+
+pub(crate) fn propose_joint_withdrawal(
+    ctx: Context<ProposeJointWithdrawal>,
+    amount: u64,
+    destination: Pubkey,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let joint_vault = &mut ctx.accounts.joint_vault;
+    let proposer = ctx.accounts.member.key();
+    joint_vault.member_index(&proposer)?;
+    require!(
+        matches!(joint_vault.policy, JointWithdrawalPolicy::Threshold { .. }),
+        ErrorCode::InvalidJointVault
+    );
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.joint_vault = joint_vault.key();
+    proposal.id = joint_vault.proposal_count;
+    proposal.amount = amount;
+    proposal.destination = destination;
+    proposal.approvals = vec![proposer];
+    proposal.executed = false;
+    proposal.bump = ctx.bumps.proposal;
+
+    joint_vault.proposal_count = joint_vault
+        .proposal_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    log_msg!(
+        "Joint withdrawal {} proposed: {} lamports to {}",
+        proposal.id,
+        amount,
+        destination
+    );
+    Ok(())
+}
+
+pub(crate) fn approve_joint_withdrawal(ctx: Context<ApproveJointWithdrawal>) -> Result<()> {
+    let member = ctx.accounts.member.key();
+    ctx.accounts.joint_vault.member_index(&member)?;
+
+    let proposal = &mut ctx.accounts.proposal;
+    require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+    require!(
+        !proposal.approvals.contains(&member),
+        ErrorCode::AlreadyApproved
+    );
+    proposal.approvals.push(member);
+
+    log_msg!(
+        "Joint withdrawal {} approved ({} approvals)",
+        proposal.id,
+        proposal.approvals.len()
+    );
+    Ok(())
+}
+
+pub(crate) fn execute_joint_withdrawal(ctx: Context<ExecuteJointWithdrawal>) -> Result<()> {
+    let joint_vault = &ctx.accounts.joint_vault;
+    joint_vault.member_index(&ctx.accounts.member.key())?;
+
+    let threshold = match joint_vault.policy {
+        JointWithdrawalPolicy::Threshold { threshold } => threshold,
+        _ => return err!(ErrorCode::InvalidJointVault),
+    };
+    let proposal = &mut ctx.accounts.proposal;
+    require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+    require!(
+        proposal.approvals.len() >= threshold as usize,
+        ErrorCode::ThresholdNotMet
+    );
+    proposal.executed = true;
+    let fee_basis_points = fee_basis_points_for(
+        &ctx.accounts.treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.member.key(),
+    )?;
+
+    pay_out_joint_vault(
+        &mut ctx.accounts.joint_vault,
+        None,
+        &ctx.accounts.funds,
+        ctx.accounts.destination.to_account_info(),
+        &ctx.accounts.treasury_config,
+        fee_basis_points,
+        &ctx.accounts.stats_shard,
+        &ctx.accounts.treasury,
+        &ctx.accounts.system_program,
+        ctx.accounts.proposal.amount,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_circle(
+    ctx: Context<CreateCircle>,
+    circle_id: u64,
+    members: Vec<Pubkey>,
+    contribution: u64,
+    collateral: u64,
+    round_duration: i64,
+    late_penalty_basis_points: u16,
+) -> Result<()> {
+    require!(
+        members.len() >= 2 && members.len() <= MAX_CIRCLE_MEMBERS,
+        ErrorCode::InvalidCircle
+    );
+    for (i, member) in members.iter().enumerate() {
+        require!(!members[..i].contains(member), ErrorCode::InvalidCircle);
+    }
+    require!(contribution > 0, ErrorCode::InvalidAmount);
+    require!(round_duration > 0, ErrorCode::InvalidCircle);
+    require!(
+        late_penalty_basis_points <= MAX_LATE_PENALTY_BASIS_POINTS,
+        ErrorCode::InvalidCircle
+    );
+
+    let circle = &mut ctx.accounts.circle;
+    circle.circle_id = circle_id;
+    circle.creator = ctx.accounts.creator.key();
+    circle.members = members
+        .into_iter()
+        .map(|key| CircleMember {
+            key,
+            joined: false,
+            collateral: 0,
+            paid_current_round: false,
+        })
+        .collect();
+    circle.contribution = contribution;
+    circle.collateral = collateral;
+    circle.round_duration = round_duration;
+    circle.late_penalty_basis_points = late_penalty_basis_points;
+    circle.current_round = 0;
+    circle.round_started_at = 0;
+    circle.pot = 0;
+    circle.status = CircleStatus::Forming;
+    circle.bump = ctx.bumps.circle;
+    circle.funds_bump = ctx.bumps.funds;
+
+    log_msg!(
+        "Savings circle {} created: {} members, {} lamports per round",
+        circle_id,
+        circle.members.len(),
+        contribution
+    );
+    Ok(())
+}
+
+pub(crate) fn join_circle(ctx: Context<CircleMemberAction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !ctx.accounts.treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+    ctx.accounts.member_config.require_active()?;
+
+    let circle = &mut ctx.accounts.circle;
+    require!(
+        circle.status == CircleStatus::Forming,
+        ErrorCode::InvalidCircleState
+    );
+    let index = circle.member_index(&ctx.accounts.member.key())?;
+    require!(!circle.members[index].joined, ErrorCode::AlreadyJoined);
+
+    if circle.collateral > 0 {
+        let collateral_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.member.to_account_info(),
+                to: ctx.accounts.funds.to_account_info(),
+            },
+        );
+        transfer(collateral_transfer, circle.collateral)?;
+    }
+    circle.members[index].joined = true;
+    circle.members[index].collateral = circle.collateral;
+
+    if circle.members.iter().all(|m| m.joined) {
+        circle.status = CircleStatus::Active;
+        circle.round_started_at = now;
+        log_msg!("Savings circle {} started", circle.circle_id);
+    }
+
+    log_msg!("Member joined savings circle {}", circle.circle_id);
+    Ok(())
+}
+
+pub(crate) fn leave_circle(ctx: Context<CircleMemberAction>) -> Result<()> {
+    let circle = &mut ctx.accounts.circle;
+    require!(
+        circle.status == CircleStatus::Forming,
+        ErrorCode::InvalidCircleState
+    );
+    let index = circle.member_index(&ctx.accounts.member.key())?;
+    require!(circle.members[index].joined, ErrorCode::NotCircleMember);
+    let amount = circle.members[index].collateral;
+    circle.members[index].joined = false;
+    circle.members[index].collateral = 0;
+
+    let circle_key = circle.key();
+    transfer_from_pda(
+        &ctx.accounts.funds,
+        ctx.accounts.member.to_account_info(),
+        &ctx.accounts.system_program,
+        &[
+            b"circle_funds".as_ref(),
+            circle_key.as_ref(),
+            &[circle.funds_bump],
+        ],
+        amount,
+    )?;
+
+    log_msg!(
+        "Member left savings circle {} ({} lamports of collateral returned)",
+        circle.circle_id,
+        amount
+    );
+    Ok(())
+}
+
+pub(crate) fn contribute_to_circle(ctx: Context<CircleMemberAction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !ctx.accounts.treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+    ctx.accounts.member_config.require_active()?;
+
+    let circle = &mut ctx.accounts.circle;
+    require!(
+        circle.status == CircleStatus::Active,
+        ErrorCode::InvalidCircleState
+    );
+    let index = circle.member_index(&ctx.accounts.member.key())?;
+    require!(
+        !circle.members[index].paid_current_round,
+        ErrorCode::AlreadyContributed
+    );
+
+    let penalty = if now > circle.round_deadline()? {
+        circle.late_penalty()?
+    } else {
+        0
+    };
+    let amount = circle
+        .contribution
+        .checked_add(penalty)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let contribution_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.member.to_account_info(),
+            to: ctx.accounts.funds.to_account_info(),
+        },
+    );
+    transfer(contribution_transfer, amount)?;
+
+    circle.members[index].paid_current_round = true;
+    circle.pot = circle.pot.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+
+    log_msg!(
+        "Contributed {} lamports to circle {} round {} (late penalty: {} lamports)",
+        amount,
+        circle.circle_id,
+        circle.current_round,
+        penalty
+    );
+    Ok(())
+}
+
+pub(crate) fn advance_circle_round(ctx: Context<AdvanceCircleRound>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let treasury_config = &ctx.accounts.treasury_config;
+    require!(
+        !treasury_config.is_paused(PAUSE_SCHEDULES, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let circle = &mut ctx.accounts.circle;
+    require!(
+        circle.status == CircleStatus::Active,
+        ErrorCode::InvalidCircleState
+    );
+    let all_paid = circle.members.iter().all(|m| m.paid_current_round);
+    require!(
+        all_paid || now > circle.round_deadline()?,
+        ErrorCode::RoundNotFinished
+    );
+
+    let recipient = circle.members[circle.current_round as usize].key;
+    require!(
+        ctx.accounts.recipient.key() == recipient,
+        ErrorCode::InvalidDestination
+    );
+
+    // Missed contributions (plus the late penalty) are covered from collateral
+    let owed = circle
+        .contribution
+        .checked_add(circle.late_penalty()?)
+        .ok_or(ErrorCode::Overflow)?;
+    let mut pot = circle.pot;
+    for member in circle.members.iter_mut() {
+        if !member.paid_current_round {
+            let forfeited = owed.min(member.collateral);
+            member.collateral -= forfeited;
+            pot = pot.checked_add(forfeited).ok_or(ErrorCode::Overflow)?;
+            log_msg!(
+                "{} forfeited {} lamports of collateral",
+                member.key,
+                forfeited
+            );
+        }
+        member.paid_current_round = false;
+    }
+
+    let fee_basis_points =
+        fee_basis_points_for(treasury_config, &ctx.accounts.fee_rate, &recipient)?;
+    let platform_fee = calculate_fee(pot, fee_basis_points)?;
+    let payout = pot.checked_sub(platform_fee).ok_or(ErrorCode::Overflow)?;
+
+    let circle_key = circle.key();
+    let seeds: &[&[u8]] = &[
+        b"circle_funds".as_ref(),
+        circle_key.as_ref(),
+        &[circle.funds_bump],
+    ];
+    transfer_from_pda(
+        &ctx.accounts.funds,
+        ctx.accounts.recipient.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        payout,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.funds,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    log_msg!(
+        "Circle {} round {} paid {} lamports to {} (fee: {} lamports)",
+        circle.circle_id,
+        circle.current_round,
+        payout,
+        recipient,
+        platform_fee
+    );
+
+    circle.pot = 0;
+    circle.current_round = circle
+        .current_round
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    if circle.current_round as usize == circle.members.len() {
+        circle.status = CircleStatus::Completed;
+        log_msg!("Savings circle {} completed", circle.circle_id);
+    } else {
+        circle.round_started_at = now;
+    }
+    Ok(())
+}
+
+pub(crate) fn withdraw_circle_collateral(ctx: Context<CircleMemberAction>) -> Result<()> {
+    let circle = &mut ctx.accounts.circle;
+    require!(
+        circle.status == CircleStatus::Completed,
+        ErrorCode::InvalidCircleState
+    );
+    let index = circle.member_index(&ctx.accounts.member.key())?;
+    let amount = circle.members[index].collateral;
+    require!(amount > 0, ErrorCode::InsufficientFunds);
+    circle.members[index].collateral = 0;
+
+    let circle_key = circle.key();
+    transfer_from_pda(
+        &ctx.accounts.funds,
+        ctx.accounts.member.to_account_info(),
+        &ctx.accounts.system_program,
+        &[
+            b"circle_funds".as_ref(),
+            circle_key.as_ref(),
+            &[circle.funds_bump],
+        ],
+        amount,
+    )?;
+
+    log_msg!(
+        "Returned {} lamports of collateral from circle {}",
+        amount,
+        circle.circle_id
+    );
+    Ok(())
+}
+
+pub(crate) fn withdraw_frozen(ctx: Context<WithdrawFrozen>, amount: u64) -> Result<()> {
+    let user_config = &ctx.accounts.user_config;
+    require!(
+        amount > 0 || user_config.pooled_shares > 0,
+        ErrorCode::InvalidAmount
+    );
+    user_config
+        .require_frozen_withdrawal_authority(&ctx.accounts.roles, ctx.accounts.authority.key)?;
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let fee_basis_points =
+        fee_basis_points_for(treasury_config, &ctx.accounts.fee_rate, &user_config.owner)?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let total_needed = amount
+        .checked_add(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        ctx.accounts.vault.lamports() >= total_needed,
+        ErrorCode::InsufficientFunds
+    );
+
+    let seeds: &[&[u8]] = &[
+        b"vault".as_ref(),
+        user_config.owner.as_ref(),
+        &[user_config.vault_bump],
+    ];
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.recovery_destination.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        amount,
+    )?;
+    transfer_from_pda(
+        &ctx.accounts.vault,
+        ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    ctx.accounts.user_config.record_withdrawn(total_needed);
+
+    // Any pooled position goes to the recovery destination in full
+    let accounts = &mut *ctx.accounts;
+    let (pooled_payout, pooled_fee) = settle_pooled_position(
+        &mut accounts.user_config,
+        &mut accounts.share_pool,
+        &accounts.share_mint,
+        &accounts.share_vault,
+        accounts.recovery_destination.to_account_info(),
+        accounts.treasury.to_account_info(),
+        &accounts.system_program,
+        fee_basis_points,
+    )?;
+
+    let mut stats_shard = accounts.stats_shard.load_mut()?;
+    stats_shard.sub_tvl(total_needed)?;
+    accounts.user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+    let amount = amount
+        .checked_add(pooled_payout)
+        .ok_or(ErrorCode::Overflow)?;
+    let platform_fee = platform_fee
+        .checked_add(pooled_fee)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit!(FrozenWithdrawal {
+        owner: accounts.user_config.owner,
+        recovery_destination: accounts.user_config.recovery_destination,
+        amount,
+        timestamp: now,
+    });
+    log_msg!(
+        "Frozen vault withdrew {} lamports to recovery destination (fee: {} lamports)",
+        amount,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn batch_deposit<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchDeposit<'info>>,
+    amounts: Vec<u64>,
+) -> Result<()> {
+    let shard_count = STATS_SHARD_COUNT as usize;
+    require!(
+        !amounts.is_empty() && ctx.remaining_accounts.len() == shard_count + amounts.len() * 2,
+        ErrorCode::InvalidBatch
+    );
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    // The payer covers the fees, so it's their negotiated rate that applies
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.payer.key(),
+    )?;
+
+    let (shard_infos, user_infos) = ctx.remaining_accounts.split_at(shard_count);
+    let stats_shards = shard_infos
+        .iter()
+        .enumerate()
+        .map(|(index, shard_info)| load_stats_shard(shard_info, index as u8, ctx.program_id))
+        .collect::<Result<Vec<_>>>()?;
+    let mut total_deposited: u64 = 0;
+    let mut total_fee: u64 = 0;
+
+    for (pair, &amount) in user_infos.chunks(2).zip(amounts.iter()) {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let (user_config_info, vault_info) = (&pair[0], &pair[1]);
+
+        let mut user_config = Account::<UserConfig>::try_from(user_config_info)?;
+        let owner = user_config.owner;
+        let expected_vault = Pubkey::create_program_address(
+            &[b"vault", owner.as_ref(), &[user_config.vault_bump]],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidBatch)?;
+        require!(vault_info.key() == expected_vault, ErrorCode::InvalidBatch);
+
+        let platform_fee = calculate_fee(amount, fee_basis_points)?;
+        let amount_after_fee = amount
+            .checked_sub(platform_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        user_config.record_deposit(
+            treasury_config,
+            vault_info.lamports(),
+            amount,
+            amount_after_fee,
+            now,
+        )?;
+
+        let vault_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: vault_info.clone(),
+            },
+        );
+        transfer(vault_transfer, amount_after_fee)?;
+        user_config.record_saved(amount_after_fee)?;
+
+        let mut stats_shard = stats_shards[stats_shard_index(&owner) as usize].load_mut()?;
+        stats_shard.add_deposit(amount_after_fee)?;
+        user_config.sync_rewards(&mut stats_shard)?;
+        user_config.exit(ctx.program_id)?;
+
+        total_deposited = total_deposited
+            .checked_add(amount_after_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        total_fee = total_fee
+            .checked_add(platform_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    if total_fee > 0 {
+        let fee_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        transfer(fee_transfer, total_fee)?;
+    }
+
+    log_msg!(
+        "Batch deposited {} lamports into {} vaults (fee: {} lamports)",
+        total_deposited,
+        amounts.len(),
+        total_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn deposit_for(
+    ctx: Context<DepositFor>,
+    beneficiary: Pubkey,
+    amount: u64,
+    memo: Option<String>,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    if let Some(memo) = &memo {
+        require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+    }
+
+    let treasury_config = &ctx.accounts.treasury_config;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_DEPOSITS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    // The payer covers the fee, so it's their negotiated rate that applies
+    let fee_basis_points = fee_basis_points_for(
+        treasury_config,
+        &ctx.accounts.fee_rate,
+        &ctx.accounts.payer.key(),
+    )?;
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    ctx.accounts.user_config.record_deposit(
+        treasury_config,
+        ctx.accounts.vault.lamports(),
+        amount,
+        amount,
+        now,
+    )?;
+    ctx.accounts.user_config.record_saved(amount)?;
+
+    if platform_fee > 0 {
+        let fee_transfer = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        transfer(fee_transfer, platform_fee)?;
+    }
+
+    let vault_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.payer.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+        },
+    );
+    transfer(vault_transfer, amount)?;
+
+    let mut stats_shard = ctx.accounts.stats_shard.load_mut()?;
+    stats_shard.add_deposit(amount)?;
+    ctx.accounts.user_config.sync_rewards(&mut stats_shard)?;
+    drop(stats_shard);
+
+    emit!(DepositedFor {
+        payer: ctx.accounts.payer.key(),
+        beneficiary,
+        amount,
+        fee: platform_fee,
+        memo,
+    });
+    log_msg!(
+        "Deposited {} lamports for {} (fee: {} lamports)",
+        amount,
+        beneficiary,
+        platform_fee
+    );
+    Ok(())
+}
+
+pub(crate) fn create_matching_program(
+    ctx: Context<CreateMatchingProgram>,
+    program_id: u64,
+    match_basis_points: u16,
+    per_user_cap: u64,
+    period_seconds: i64,
+) -> Result<()> {
+    require!(
+        match_basis_points > 0 && match_basis_points <= MAX_MATCH_BASIS_POINTS,
+        ErrorCode::InvalidMatchingProgram
+    );
+    require!(
+        per_user_cap > 0 && period_seconds > 0,
+        ErrorCode::InvalidMatchingProgram
+    );
+
+    let matching_program = &mut ctx.accounts.matching_program;
+    matching_program.sponsor = ctx.accounts.sponsor.key();
+    matching_program.program_id = program_id;
+    matching_program.match_basis_points = match_basis_points;
+    matching_program.per_user_cap = per_user_cap;
+    matching_program.period_seconds = period_seconds;
+    matching_program.total_matched = 0;
+    matching_program.bump = ctx.bumps.matching_program;
+    matching_program.funds_bump = ctx.bumps.funds;
+
+    log_msg!(
+        "Matching program {} created: {} bps, cap {} lamports per {}s",
+        program_id,
+        match_basis_points,
+        per_user_cap,
+        period_seconds
+    );
+    Ok(())
+}
+
+pub(crate) fn fund_matching_program(
+    ctx: Context<ManageMatchingProgram>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let fund_transfer = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.sponsor.to_account_info(),
+            to: ctx.accounts.funds.to_account_info(),
+        },
+    );
+    transfer(fund_transfer, amount)?;
+
+    log_msg!("Matching program funded with {} lamports", amount);
+    Ok(())
+}
+
+pub(crate) fn withdraw_matching_budget(
+    ctx: Context<ManageMatchingProgram>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let available = ctx
+        .accounts
+        .funds
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    require!(amount <= available, ErrorCode::InsufficientFunds);
+    transfer_from_pda(
+        &ctx.accounts.funds,
+        ctx.accounts.sponsor.to_account_info(),
+        &ctx.accounts.system_program,
+        &[
+            b"matching_funds".as_ref(),
+            ctx.accounts.matching_program.key().as_ref(),
+            &[ctx.accounts.matching_program.funds_bump],
+        ],
+        amount,
+    )?;
+
+    log_msg!("Withdrew {} lamports of unspent matching budget", amount);
+    Ok(())
+}
+
+pub(crate) fn join_matching_program(ctx: Context<JoinMatchingProgram>) -> Result<()> {
+    let matching_participant = &mut ctx.accounts.matching_participant;
+    matching_participant.matching_program = ctx.accounts.matching_program.key();
+    matching_participant.user = ctx.accounts.user.key();
+    matching_participant.period_start = Clock::get()?.unix_timestamp;
+    matching_participant.matched_in_period = 0;
+    matching_participant.bump = ctx.bumps.matching_participant;
+
+    ctx.accounts
+        .user_config
+        .record_activity(matching_participant.period_start);
+
+    log_msg!(
+        "Joined matching program {}",
+        ctx.accounts.matching_program.program_id
+    );
+    Ok(())
+}
+
+pub(crate) fn redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+    require!(points > 0, ErrorCode::InvalidAmount);
+    let user_config = &mut ctx.accounts.user_config;
+    require!(
+        points <= user_config.reward_points,
+        ErrorCode::InsufficientPoints
+    );
+    let credit = points
+        .checked_mul(LAMPORTS_PER_POINT)
+        .ok_or(ErrorCode::Overflow)?;
+    user_config.reward_points -= points;
+    user_config.fee_credit = user_config
+        .fee_credit
+        .checked_add(credit)
+        .ok_or(ErrorCode::Overflow)?;
+    user_config.record_activity(Clock::get()?.unix_timestamp);
+
+    log_msg!(
+        "Redeemed {} points for {} lamports of fee credit",
+        points,
+        credit
+    );
+    Ok(())
+}
+
+pub(crate) fn set_savings_goal(ctx: Context<SetSavingsGoal>, savings_goal: u64) -> Result<()> {
+    require!(
+        savings_goal == 0 || savings_goal >= MIN_SAVINGS_GOAL_LAMPORTS,
+        ErrorCode::InvalidSavingsGoal
+    );
+    let user_config = &mut ctx.accounts.user_config;
+    user_config.savings_goal = savings_goal;
+    user_config.record_activity(Clock::get()?.unix_timestamp);
+
+    log_msg!("Savings goal set to {} lamports", savings_goal);
+    Ok(())
+}
+
+/// Move lamports out of a system-owned PDA (a vault, the treasury or a funds
+/// account), signing with its seeds (no-op for 0)
+pub(crate) fn transfer_from_pda<'info>(
+    from: &SystemAccount<'info>,
+    to: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let signer_seeds = &[seeds];
+    let pda_transfer = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        Transfer {
+            from: from.to_account_info(),
+            to,
+        },
+        signer_seeds,
+    );
+    transfer(pda_transfer, amount)
+}
+
+/// Pay `amount` plus the platform fee out of a joint vault and update TVL. The whole
+/// debit, fee included, is charged to the withdrawing member when there is one; under
+/// the Proportional policy it must fit in what they have contributed and not yet
+/// withdrawn.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pay_out_joint_vault<'info>(
+    joint_vault: &mut Account<'info, JointVault>,
+    member_index: Option<usize>,
+    funds: &SystemAccount<'info>,
+    to: AccountInfo<'info>,
+    treasury_config: &TreasuryConfig,
+    fee_basis_points: u16,
+    stats_shard: &AccountLoader<'info, StatsShard>,
+    treasury: &SystemAccount<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !treasury_config.is_paused(PAUSE_WITHDRAWALS, now),
+        ErrorCode::ProtocolPaused
+    );
+
+    let platform_fee = calculate_fee(amount, fee_basis_points)?;
+    let total_needed = amount
+        .checked_add(platform_fee)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        funds.lamports() >= total_needed,
+        ErrorCode::InsufficientFunds
+    );
+
+    if let Some(member_index) = member_index {
+        let proportional = matches!(joint_vault.policy, JointWithdrawalPolicy::Proportional);
+        let member = &mut joint_vault.members[member_index];
+        let withdrawn = member
+            .withdrawn
+            .checked_add(total_needed)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            !proportional || withdrawn <= member.contributed,
+            ErrorCode::ExceedsContribution
+        );
+        member.withdrawn = withdrawn;
+    }
+
+    let joint_vault_key = joint_vault.key();
+    let funds_bump = joint_vault.funds_bump;
+    let seeds: &[&[u8]] = &[
+        b"joint_vault_funds".as_ref(),
+        joint_vault_key.as_ref(),
+        &[funds_bump],
+    ];
+    transfer_from_pda(funds, to, system_program, seeds, amount)?;
+    transfer_from_pda(
+        funds,
+        treasury.to_account_info(),
+        system_program,
+        seeds,
+        platform_fee,
+    )?;
+
+    stats_shard.load_mut()?.sub_tvl(total_needed)?;
+
+    log_msg!(
+        "Withdrawn {} lamports from joint vault (fee: {} lamports)",
+        amount,
+        platform_fee
+    );
+    Ok(())
+}
+
+/// Check a sorted-pair keccak Merkle proof for `user` against the allowlist root
+pub(crate) fn verify_allowlist_proof(user: &Pubkey, proof: &[[u8; 32]], root: [u8; 32]) -> bool {
+    let mut node = hashv(&[user.as_ref()]).0;
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).0
+        } else {
+            hashv(&[sibling, &node]).0
+        };
+    }
+    node == root
+}
+
+/// Pay a sponsor match for `deposited` lamports, bounded by the user's cap for the
+/// current period and the program's remaining budget. Returns the amount matched.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pay_match<'info>(
+    matching_program: &mut Account<'info, MatchingProgram>,
+    funds: &SystemAccount<'info>,
+    matching_participant: &mut Account<'info, MatchingParticipant>,
+    vault: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    user: &Pubkey,
+    deposited: u64,
+    now: i64,
+) -> Result<u64> {
+    let matching_program_key = matching_program.key();
+    require!(
+        matching_participant.matching_program == matching_program_key
+            && matching_participant.user == *user,
+        ErrorCode::InvalidMatchingProgram
+    );
+    let expected_funds = Pubkey::create_program_address(
+        &[
+            b"matching_funds",
+            matching_program_key.as_ref(),
+            &[matching_program.funds_bump],
+        ],
+        &crate::ID,
+    )
+    .map_err(|_| ErrorCode::InvalidMatchingProgram)?;
+    require!(
+        funds.key() == expected_funds,
+        ErrorCode::InvalidMatchingProgram
+    );
+
+    matching_participant.roll_period(now, matching_program.period_seconds)?;
+    let desired = (deposited as u128)
+        .checked_mul(matching_program.match_basis_points as u128)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(BASIS_POINTS_DIVISOR as u128)
+        .ok_or(ErrorCode::Overflow)? as u64;
+    let remaining_cap = matching_program
+        .per_user_cap
+        .saturating_sub(matching_participant.matched_in_period);
+    // An empty budget just means no match, not a failed deposit
+    let budget = funds
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    let matched = desired.min(remaining_cap).min(budget);
+    if matched == 0 {
+        return Ok(0);
+    }
+
+    transfer_from_pda(
+        funds,
+        vault,
+        system_program,
+        &[
+            b"matching_funds".as_ref(),
+            matching_program_key.as_ref(),
+            &[matching_program.funds_bump],
+        ],
+        matched,
+    )?;
+    matching_participant.matched_in_period = matching_participant
+        .matched_in_period
+        .checked_add(matched)
+        .ok_or(ErrorCode::Overflow)?;
+    matching_program.total_matched = matching_program
+        .total_matched
+        .checked_add(matched)
+        .ok_or(ErrorCode::Overflow)?;
+
+    log_msg!("Sponsor matched {} lamports", matched);
+    Ok(matched)
+}
+
+// Account Structures
+#[derive(Accounts)]
+pub struct InitializeUser<'info> {
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserConfig::INIT_SPACE,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"vault", user.key().as_ref()],
+        bump
+    )]
+    /// CHECK: User's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    /// Registered onboarding verifier co-signing the user's admission
+    pub verifier: Option<Signer<'info>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", owner.key().as_ref()],
+        bump
+    )]
+    /// CHECK: May still be in an older layout; decoded after it is grown
+    pub user_config: UncheckedAccount<'info>,
+
+    /// CHECK: Only used to derive the user_config address
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"vault", owner.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Owner's savings vault PDA, seeded into its stats shard
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&owner.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: User's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Owner field in user_config
+    pub owner: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional sponsor match: pass all three accounts or none
+    #[account(mut)]
+    pub matching_program: Option<Account<'info, MatchingProgram>>,
+    #[account(mut)]
+    /// CHECK: Matching program funds PDA, verified against `matching_program`
+    pub matching_funds: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub matching_participant: Option<Account<'info, MatchingParticipant>>,
+
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: User's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Owner field in user_config
+    pub owner: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRecoveryGuardians<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RecoveryConfig::INIT_SPACE,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianRecoveryAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"recovery", recovery_config.owner.as_ref()],
+        bump = recovery_config.bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump = recovery_config.bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRecovery<'info> {
+    #[account(
+        mut,
+        close = new_owner,
+        seeds = [b"recovery", old_owner.key().as_ref()],
+        bump = recovery_config.bump,
+        constraint = recovery_config.pending_owner == new_owner.key()
+            @ ErrorCode::InvalidRecoveryOwner
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    /// CHECK: Bound to recovery_config and old_user_config through their seeds
+    pub old_owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_config", old_owner.key().as_ref()],
+        bump = old_user_config.bump
+    )]
+    pub old_user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", old_owner.key().as_ref()],
+        bump = old_user_config.vault_bump
+    )]
+    /// CHECK: Lost owner's savings vault PDA
+    pub old_vault: SystemAccount<'info>,
+
+    #[account(
+        init,
+        payer = new_owner,
+        space = 8 + UserConfig::INIT_SPACE,
+        seeds = [b"user_config", new_owner.key().as_ref()],
+        bump
+    )]
+    pub new_user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", new_owner.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Recovered owner's savings vault PDA
+    pub new_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&old_owner.key())].as_ref()],
+        bump = old_stats_shard.load()?.bump
+    )]
+    pub old_stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&new_owner.key())].as_ref()],
+        bump = new_stats_shard.load()?.bump
+    )]
+    pub new_stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRecoveredAllowance<'info> {
+    /// CHECK: Bound to old_user_config and allowance through their seeds
+    pub old_owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"user_config", old_owner.key().as_ref()],
+        bump = old_user_config.bump
+    )]
+    pub old_user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        close = new_owner,
+        seeds = [b"allowance", old_owner.key().as_ref(), allowance.spender.as_ref()],
+        bump = allowance.bump
+    )]
+    pub allowance: Account<'info, WithdrawAllowance>,
+
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetBeneficiary<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimInheritance<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user_config.owner.as_ref()],
+        bump = user_config.bump,
+        constraint = user_config.beneficiary == beneficiary.key() @ ErrorCode::Unauthorized
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", user_config.owner.as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: Inactive owner's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user_config.owner)].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    /// Share pool accounts; required when the owner holds pooled shares
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump
+    )]
+    pub share_pool: Option<Account<'info, SharePool>>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump
+    )]
+    pub share_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrantAllowance<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + WithdrawAllowance::INIT_SPACE,
+        seeds = [b"allowance", user.key().as_ref(), spender.key().as_ref()],
+        bump
+    )]
+    pub allowance: Account<'info, WithdrawAllowance>,
+
+    /// CHECK: Key being granted the allowance; never signs here
+    pub spender: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAllowance<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"allowance", user.key().as_ref(), allowance.spender.as_ref()],
+        bump = allowance.bump
+    )]
+    pub allowance: Account<'info, WithdrawAllowance>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithAllowance<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", allowance.owner.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"allowance", allowance.owner.as_ref(), spender.key().as_ref()],
+        bump = allowance.bump
+    )]
+    pub allowance: Account<'info, WithdrawAllowance>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", allowance.owner.as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: Owner's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&allowance.owner)].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    /// CHECK: Must be the destination fixed by the owner in the allowance
+    #[account(
+        mut,
+        address = allowance.destination @ ErrorCode::InvalidDestination
+    )]
+    pub destination: UncheckedAccount<'info>,
+
+    pub spender: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct CreateJointVault<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + JointVault::INIT_SPACE,
+        seeds = [b"joint_vault", creator.key().as_ref(), vault_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub joint_vault: Account<'info, JointVault>,
+
+    #[account(
+        seeds = [b"joint_vault_funds", joint_vault.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Joint vault SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JointDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"joint_vault",
+            joint_vault.creator.as_ref(),
+            joint_vault.vault_id.to_le_bytes().as_ref()
+        ],
+        bump = joint_vault.bump
+    )]
+    pub joint_vault: Account<'info, JointVault>,
+
+    #[account(
+        mut,
+        seeds = [b"joint_vault_funds", joint_vault.key().as_ref()],
+        bump = joint_vault.funds_bump
+    )]
+    /// CHECK: Joint vault SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_config", member.key().as_ref()],
+        bump = member_config.bump
+    )]
+    pub member_config: Account<'info, UserConfig>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&joint_vault.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct JointWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"joint_vault",
+            joint_vault.creator.as_ref(),
+            joint_vault.vault_id.to_le_bytes().as_ref()
+        ],
+        bump = joint_vault.bump
+    )]
+    pub joint_vault: Account<'info, JointVault>,
+
+    #[account(
+        mut,
+        seeds = [b"joint_vault_funds", joint_vault.key().as_ref()],
+        bump = joint_vault.funds_bump
+    )]
+    /// CHECK: Joint vault SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&joint_vault.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeJointWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"joint_vault",
+            joint_vault.creator.as_ref(),
+            joint_vault.vault_id.to_le_bytes().as_ref()
+        ],
+        bump = joint_vault.bump
+    )]
+    pub joint_vault: Account<'info, JointVault>,
+
+    #[account(
+        init,
+        payer = member,
+        space = 8 + JointWithdrawalProposal::INIT_SPACE,
+        seeds = [
+            b"joint_withdrawal",
+            joint_vault.key().as_ref(),
+            joint_vault.proposal_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, JointWithdrawalProposal>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveJointWithdrawal<'info> {
+    #[account(
+        seeds = [
+            b"joint_vault",
+            joint_vault.creator.as_ref(),
+            joint_vault.vault_id.to_le_bytes().as_ref()
+        ],
+        bump = joint_vault.bump
+    )]
+    pub joint_vault: Account<'info, JointVault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"joint_withdrawal",
+            joint_vault.key().as_ref(),
+            proposal.id.to_le_bytes().as_ref()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, JointWithdrawalProposal>,
+
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteJointWithdrawal<'info> {
+    #[account(
+        seeds = [
+            b"joint_vault",
+            joint_vault.creator.as_ref(),
+            joint_vault.vault_id.to_le_bytes().as_ref()
+        ],
+        bump = joint_vault.bump
+    )]
+    pub joint_vault: Account<'info, JointVault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"joint_withdrawal",
+            joint_vault.key().as_ref(),
+            proposal.id.to_le_bytes().as_ref()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, JointWithdrawalProposal>,
+
+    #[account(
+        mut,
+        seeds = [b"joint_vault_funds", joint_vault.key().as_ref()],
+        bump = joint_vault.funds_bump
+    )]
+    /// CHECK: Joint vault SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&joint_vault.key())].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Must be the destination named in the proposal
+    #[account(
+        mut,
+        address = proposal.destination @ ErrorCode::InvalidDestination
+    )]
+    pub destination: UncheckedAccount<'info>,
+
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(circle_id: u64)]
+pub struct CreateCircle<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + SavingsCircle::INIT_SPACE,
+        seeds = [b"savings_circle", creator.key().as_ref(), circle_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub circle: Account<'info, SavingsCircle>,
+
+    #[account(
+        seeds = [b"circle_funds", circle.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Savings circle SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CircleMemberAction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"savings_circle",
+            circle.creator.as_ref(),
+            circle.circle_id.to_le_bytes().as_ref()
+        ],
+        bump = circle.bump
+    )]
+    pub circle: Account<'info, SavingsCircle>,
+
+    #[account(
+        mut,
+        seeds = [b"circle_funds", circle.key().as_ref()],
+        bump = circle.funds_bump
+    )]
+    /// CHECK: Savings circle SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        seeds = [b"user_config", member.key().as_ref()],
+        bump = member_config.bump
+    )]
+    pub member_config: Account<'info, UserConfig>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdvanceCircleRound<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"savings_circle",
+            circle.creator.as_ref(),
+            circle.circle_id.to_le_bytes().as_ref()
+        ],
+        bump = circle.bump
+    )]
+    pub circle: Account<'info, SavingsCircle>,
+
+    #[account(
+        mut,
+        seeds = [b"circle_funds", circle.key().as_ref()],
+        bump = circle.funds_bump
+    )]
+    /// CHECK: Savings circle SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Must be the member scheduled to receive this round's pot
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub cranker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFrozen<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user_config.owner.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", user_config.owner.as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: User's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&user_config.owner)].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(
+        mut,
+        address = user_config.recovery_destination @ ErrorCode::InvalidDestination
+    )]
+    /// CHECK: Recovery path designated when the vault was frozen
+    pub recovery_destination: UncheckedAccount<'info>,
+
+    /// Share pool accounts; required when the owner holds pooled shares
+    #[account(
+        mut,
+        seeds = [b"share_pool"],
+        bump
+    )]
+    pub share_pool: Option<Account<'info, SharePool>>,
+
+    #[account(
+        seeds = [b"share_mint"],
+        bump
+    )]
+    pub share_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_vault"],
+        bump
+    )]
+    /// CHECK: Pooled SOL holder PDA
+    pub share_vault: Option<SystemAccount<'info>>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = roles.bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    /// Owner or risk manager
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Optional negotiated fee rate for the user or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BatchDeposit<'info> {
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the payer or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey)]
+pub struct DepositFor<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", beneficiary.as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", beneficiary.as_ref()],
+        bump = user_config.vault_bump
+    )]
+    /// CHECK: Beneficiary's savings vault PDA
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury_config"],
+        bump = treasury_config.bump
+    )]
+    pub treasury_config: Account<'info, TreasuryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_vault"],
+        bump = treasury_config.treasury_bump
+    )]
+    /// CHECK: Treasury PDA
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stats_shard", [stats_shard_index(&beneficiary)].as_ref()],
+        bump = stats_shard.load()?.bump
+    )]
+    pub stats_shard: AccountLoader<'info, StatsShard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Optional negotiated fee rate for the payer or the calling partner program
+    pub fee_rate: FeeRate<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: u64)]
+pub struct CreateMatchingProgram<'info> {
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + MatchingProgram::INIT_SPACE,
+        seeds = [b"matching_program", sponsor.key().as_ref(), program_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub matching_program: Account<'info, MatchingProgram>,
+
+    #[account(
+        seeds = [b"matching_funds", matching_program.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Matching budget SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageMatchingProgram<'info> {
+    #[account(
+        seeds = [
+            b"matching_program",
+            sponsor.key().as_ref(),
+            matching_program.program_id.to_le_bytes().as_ref()
+        ],
+        bump = matching_program.bump
+    )]
+    pub matching_program: Account<'info, MatchingProgram>,
+
+    #[account(
+        mut,
+        seeds = [b"matching_funds", matching_program.key().as_ref()],
+        bump = matching_program.funds_bump
+    )]
+    /// CHECK: Matching budget SOL holder PDA
+    pub funds: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinMatchingProgram<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(has_one = sponsor @ ErrorCode::Unauthorized)]
+    pub matching_program: Account<'info, MatchingProgram>,
+
+    pub sponsor: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + MatchingParticipant::INIT_SPACE,
+        seeds = [b"matching_participant", matching_program.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub matching_participant: Account<'info, MatchingParticipant>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemPoints<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSavingsGoal<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_config", user.key().as_ref()],
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub user: Signer<'info>,
+}
@@ -0,0 +1,385 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use auto_savings::{UserConfig, UserStatus};
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
+};
+use spl_token_2022::extension::{
+    transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+
+declare_id!("E6PnXnUhFphvBYDrWENCWZXaTunKG94MNScjKVkPqmL9");
+
+// Extra accounts appended to every Execute: auto-savings program, user_config, pending_savings
+const EXTRA_ACCOUNT_COUNT: usize = 3;
+
+// Token-2022 Execute account indices
+const SOURCE_INDEX: u8 = 0;
+const MINT_INDEX: u8 = 1;
+const AUTO_SAVINGS_PROGRAM_INDEX: u8 = 5;
+
+// Offset of the owner field in SPL token account data. Savings PDAs are derived from
+// the source account's owner, not the transfer authority, which may be a delegate.
+const TOKEN_ACCOUNT_OWNER_OFFSET: u8 = 32;
+
+/// Transfer hook for mints we issue.
+///
+/// Token-2022 hands every account to a hook read-only, so the hook cannot move the
+/// sender's tokens itself. Instead each transfer accrues `savings_rate`% of the amount
+/// into the sender's `PendingSavings`. A transfer from the sender into their auto-savings
+/// token vault settles it, as does auto-savings' `settle_hook_savings` crank, which
+/// pulls the tokens in as the sender's approved delegate and reports through
+/// `record_settlement`.
+#[program]
+pub mod savings_hook {
+    use super::*;
+
+    /// Write the extra account list Token-2022 resolves for each transfer (mint authority only)
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let extra_account_metas = extra_account_metas()?;
+        let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas)?;
+
+        msg!(
+            "Savings hook registered for mint {}",
+            ctx.accounts.mint.key()
+        );
+        Ok(())
+    }
+
+    /// Opt in to hook savings for a mint
+    pub fn enable_hook_savings(ctx: Context<EnableHookSavings>) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let mint = ctx.accounts.mint.key();
+        let (token_vault, _) = Pubkey::find_program_address(
+            &[b"token_vault", user.as_ref(), mint.as_ref()],
+            &auto_savings::ID,
+        );
+
+        let pending_savings = &mut ctx.accounts.pending_savings;
+        pending_savings.owner = user;
+        pending_savings.mint = mint;
+        pending_savings.token_vault = token_vault;
+        pending_savings.pending = 0;
+        pending_savings.total_settled = 0;
+        pending_savings.bump = ctx.bumps.pending_savings;
+
+        msg!("Hook savings enabled for mint {}", mint);
+        Ok(())
+    }
+
+    /// Count tokens auto-savings pulled into the owner's token vault against what they
+    /// owe. Only the vault PDA can sign for this, so only auto-savings can call it.
+    pub fn record_settlement(ctx: Context<RecordSettlement>, amount: u64) -> Result<()> {
+        let pending_savings = &mut ctx.accounts.pending_savings;
+        require!(amount <= pending_savings.pending, ErrorCode::ExceedsPending);
+        pending_savings.pending -= amount;
+        pending_savings.total_settled = pending_savings
+            .total_settled
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        msg!("Settled {} of pending savings", amount);
+        Ok(())
+    }
+
+    /// Stop hook savings for a mint and reclaim the rent. The owner can close it, and so
+    /// can the new owner once the vault has been recovered away from a lost wallet.
+    pub fn close_pending_savings(ctx: Context<ClosePendingSavings>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let user_config = &ctx.accounts.user_config;
+        require!(
+            authority == ctx.accounts.pending_savings.owner
+                || (user_config.status == UserStatus::Recovered
+                    && user_config.recovery_destination == authority),
+            ErrorCode::Unauthorized
+        );
+
+        msg!(
+            "Hook savings closed for mint {}",
+            ctx.accounts.pending_savings.mint
+        );
+        Ok(())
+    }
+
+    /// SPL transfer-hook `Execute`, reached through `fallback`
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        assert_is_transferring(&ctx.accounts.source_token.to_account_info())?;
+
+        // Senders who never opted in transfer as normal
+        let pending_info = ctx.accounts.pending_savings.to_account_info();
+        if pending_info.owner != &crate::ID || pending_info.data_is_empty() {
+            return Ok(());
+        }
+        let mut pending_savings =
+            PendingSavings::try_deserialize(&mut &pending_info.try_borrow_data()?[..])?;
+
+        if ctx.accounts.destination_token.key() == pending_savings.token_vault {
+            // auto-savings' crank moves tokens as the vault, having already reported
+            // them through `record_settlement`
+            if ctx.accounts.owner.key() == pending_savings.token_vault {
+                return Ok(());
+            }
+            // Sender is paying into their own vault: settle what they owe
+            pending_savings.pending = pending_savings.pending.saturating_sub(amount);
+            pending_savings.total_settled = pending_savings
+                .total_settled
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+            msg!("Settled {} of pending savings", amount);
+        } else {
+            let user_config_info = ctx.accounts.user_config.to_account_info();
+            require!(
+                user_config_info.owner == &auto_savings::ID,
+                ErrorCode::Unauthorized
+            );
+            let user_config =
+                UserConfig::try_deserialize(&mut &user_config_info.try_borrow_data()?[..])?;
+            let owed = (amount as u128)
+                .checked_mul(user_config.savings_rate as u128)
+                .ok_or(ErrorCode::Overflow)?
+                .checked_div(100)
+                .ok_or(ErrorCode::Overflow)? as u64;
+            pending_savings.pending = pending_savings
+                .pending
+                .checked_add(owed)
+                .ok_or(ErrorCode::Overflow)?;
+            msg!(
+                "Accrued {} pending savings ({}%)",
+                owed,
+                user_config.savings_rate
+            );
+        }
+
+        let mut data = pending_info.try_borrow_mut_data()?;
+        pending_savings.try_serialize(&mut &mut data[..])
+    }
+
+    /// Route the SPL transfer-hook interface instructions to their Anchor handlers
+    pub fn fallback<'info>(
+        program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        match TransferHookInstruction::unpack(data)? {
+            TransferHookInstruction::Execute { amount } => {
+                let amount_bytes = amount.to_le_bytes();
+                __private::__global::transfer_hook(program_id, accounts, &amount_bytes)
+            }
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+}
+
+/// Accounts Token-2022 appends to each Execute, after the validation account (index 4)
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![
+        ExtraAccountMeta::new_with_pubkey(&auto_savings::ID, false, false)?,
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            AUTO_SAVINGS_PROGRAM_INDEX,
+            &[
+                Seed::Literal {
+                    bytes: b"user_config".to_vec(),
+                },
+                Seed::AccountData {
+                    account_index: SOURCE_INDEX,
+                    data_index: TOKEN_ACCOUNT_OWNER_OFFSET,
+                    length: 32,
+                },
+            ],
+            false,
+            false,
+        )?,
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"pending_savings".to_vec(),
+                },
+                Seed::AccountData {
+                    account_index: SOURCE_INDEX,
+                    data_index: TOKEN_ACCOUNT_OWNER_OFFSET,
+                    length: 32,
+                },
+                Seed::AccountKey { index: MINT_INDEX },
+            ],
+            false,
+            true,
+        )?,
+    ])
+}
+
+/// Reject direct calls: Token-2022 sets `transferring` on the source only mid-transfer
+fn assert_is_transferring(source_token: &AccountInfo) -> Result<()> {
+    let data = source_token.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    let extension = account.get_extension::<TransferHookAccount>()?;
+    require!(
+        bool::from(extension.transferring),
+        ErrorCode::NotTransferring
+    );
+    Ok(())
+}
+
+// Account Structures
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ExtraAccountMetaList::size_of(EXTRA_ACCOUNT_COUNT)?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: TLV account written by ExtraAccountMetaList::init
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = mint.mint_authority == Some(authority.key()).into()
+            @ ErrorCode::Unauthorized
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnableHookSavings<'info> {
+    #[account(
+        seeds = [b"user_config", user.key().as_ref()],
+        seeds::program = auto_savings::ID,
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PendingSavings::INIT_SPACE,
+        seeds = [b"pending_savings", user.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub pending_savings: Account<'info, PendingSavings>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePendingSavings<'info> {
+    #[account(
+        seeds = [b"user_config", pending_savings.owner.as_ref()],
+        seeds::program = auto_savings::ID,
+        bump = user_config.bump
+    )]
+    pub user_config: Account<'info, UserConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            b"pending_savings",
+            pending_savings.owner.as_ref(),
+            pending_savings.mint.as_ref()
+        ],
+        bump = pending_savings.bump
+    )]
+    pub pending_savings: Account<'info, PendingSavings>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordSettlement<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"pending_savings",
+            pending_savings.owner.as_ref(),
+            pending_savings.mint.as_ref()
+        ],
+        bump = pending_savings.bump,
+        has_one = token_vault @ ErrorCode::Unauthorized
+    )]
+    pub pending_savings: Account<'info, PendingSavings>,
+
+    /// Owner's auto-savings token vault, signed for by auto-savings
+    pub token_vault: Signer<'info>,
+}
+
+/// Account order is fixed by the transfer-hook interface
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Source owner or delegate (the transfer authority)
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Validation account, resolved by Token-2022
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(address = auto_savings::ID)]
+    /// CHECK: auto-savings program, needed to derive user_config
+    pub auto_savings_program: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"user_config", source_token.owner.as_ref()],
+        seeds::program = auto_savings::ID,
+        bump
+    )]
+    /// CHECK: Sender's auto-savings config; may not exist for non-savers
+    pub user_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_savings", source_token.owner.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Sender's pending savings; may not exist if they never opted in
+    pub pending_savings: UncheckedAccount<'info>,
+}
+
+// Data Structures
+#[account]
+#[derive(InitSpace)]
+pub struct PendingSavings {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Owner's auto-savings token vault; transfers into it settle `pending`
+    pub token_vault: Pubkey,
+    /// Tokens owed to savings but not yet moved into the vault
+    pub pending: u64,
+    pub total_settled: u64,
+    pub bump: u8,
+}
+
+// Error Codes
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Hook can only be invoked during a token transfer")]
+    NotTransferring,
+    #[msg("Amount exceeds pending savings")]
+    ExceedsPending,
+}
@@ -0,0 +1,133 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, SystemProgram, Transaction } from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import {
+  admin,
+  createUser,
+  exists,
+  expectError,
+  pda,
+  program,
+  provider,
+  rolesPDA,
+  setupProtocol,
+  setupSharePool,
+  shareMintPDA,
+  sharePoolPDA,
+  shareVaultPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+} from "./fixture";
+
+describe("achievements", () => {
+  const URI_BASE = "https://example.com/achievements/";
+  const ONE_SOL_SAVED = 1;
+
+  let user: Keypair;
+
+  const setAchievementUriBase = (uriBase: string) =>
+    program.methods
+      .setAchievementUriBase(uriBase)
+      .accounts({ roles: rolesPDA, treasuryConfig: treasuryConfigPDA, admin: admin.publicKey })
+      .rpc();
+
+  const achievementMintPDA = (milestoneIndex: number) =>
+    pda(Buffer.from("achievement_mint"), user.publicKey.toBuffer(), Buffer.from([milestoneIndex]));
+
+  const claimOneSolSaved = () => {
+    const achievementMint = achievementMintPDA(ONE_SOL_SAVED);
+    return program.methods
+      .claimAchievement({ oneSolSaved: {} })
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        achievement: pda(
+          Buffer.from("achievement"),
+          user.publicKey.toBuffer(),
+          Buffer.from([ONE_SOL_SAVED])
+        ),
+        achievementMint,
+        userTokenAccount: getAssociatedTokenAddressSync(
+          achievementMint,
+          user.publicKey,
+          false,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        user: user.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+  };
+
+  before(async () => {
+    await setupProtocol();
+    await setupSharePool();
+    user = await createUser();
+  });
+
+  it("Refuses claims until the metadata URI base is set", async () => {
+    await setAchievementUriBase("");
+    await expectError(claimOneSolSaved(), "AchievementsNotConfigured");
+    await setAchievementUriBase(URI_BASE);
+  });
+
+  it("Rejects savings goals below the minimum", async () => {
+    await expectError(
+      program.methods
+        .setSavingsGoal(new anchor.BN(0.05 * LAMPORTS_PER_SOL))
+        .accounts({ userConfig: userAccounts(user.publicKey).userConfig, user: user.publicKey })
+        .signers([user])
+        .rpc(),
+      "InvalidSavingsGoal"
+    );
+  });
+
+  it("Does not count lamports sent straight to the vault", async () => {
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: admin.publicKey,
+          toPubkey: userAccounts(user.publicKey).vault,
+          lamports: 2 * LAMPORTS_PER_SOL,
+        })
+      )
+    );
+    await expectError(claimOneSolSaved(), "MilestoneNotReached");
+  });
+
+  it("Counts pooled deposits and mints the badge with its metadata URI", async () => {
+    await program.methods
+      .depositPooled(new anchor.BN(1.1 * LAMPORTS_PER_SOL))
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        shareVault: shareVaultPDA,
+        statsShard: userAccounts(user.publicKey).statsShard,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    await claimOneSolSaved();
+
+    const mint = await provider.connection.getAccountInfo(achievementMintPDA(ONE_SOL_SAVED));
+    assert.isTrue(mint.data.includes(Buffer.from(`${URI_BASE}one-sol-saved.json`)));
+    assert.isTrue(
+      await exists(
+        pda(Buffer.from("achievement"), user.publicKey.toBuffer(), Buffer.from([ONE_SOL_SAVED]))
+      )
+    );
+  });
+});
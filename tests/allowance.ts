@@ -0,0 +1,115 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  balance,
+  createUser,
+  deposit,
+  expectError,
+  exists,
+  feeOverrideAccounts,
+  fundedKeypair,
+  pda,
+  program,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  withFeeWaived,
+} from "./fixture";
+
+describe("allowance", () => {
+  const PER_PERIOD = 0.2 * LAMPORTS_PER_SOL;
+
+  let owner: Keypair;
+  let spender: Keypair;
+  const destination = Keypair.generate().publicKey;
+
+  const allowancePDA = () =>
+    pda(Buffer.from("allowance"), owner.publicKey.toBuffer(), spender.publicKey.toBuffer());
+
+  const withdrawWithAllowance = (
+    lamports: number,
+    to: PublicKey = destination,
+    feeOverride: object = {}
+  ) =>
+    program.methods
+      .withdrawWithAllowance(new anchor.BN(lamports))
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        allowance: allowancePDA(),
+        vault: userAccounts(owner.publicKey).vault,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        statsShard: userAccounts(owner.publicKey).statsShard,
+        destination: to,
+        spender: spender.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...feeOverride,
+      })
+      .signers([spender])
+      .rpc();
+
+  const grantAllowance = () =>
+    program.methods
+      .grantAllowance(destination, new anchor.BN(PER_PERIOD), new anchor.BN(3600))
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        allowance: allowancePDA(),
+        spender: spender.publicKey,
+        user: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    owner = await createUser();
+    spender = await fundedKeypair();
+    await deposit(2 * LAMPORTS_PER_SOL, owner);
+    await grantAllowance();
+  });
+
+  it("Lets the spender withdraw to the fixed destination within the period limit", async () => {
+    await withdrawWithAllowance(PER_PERIOD / 2);
+    assert.equal(await balance(destination), PER_PERIOD / 2);
+
+    await expectError(withdrawWithAllowance(PER_PERIOD), "AllowanceExceeded");
+    await expectError(
+      withdrawWithAllowance(1000, spender.publicKey),
+      "InvalidDestination"
+    );
+
+    await withdrawWithAllowance(PER_PERIOD / 2);
+    const allowance = await program.account.withdrawAllowance.fetch(allowancePDA());
+    assert.isTrue(allowance.withdrawnInPeriod.eqn(PER_PERIOD));
+  });
+
+  it("Charges allowance withdrawals at the owner's negotiated rate", async () => {
+    // A fresh spender, since the first test used up this period's allowance
+    spender = await fundedKeypair();
+    await grantAllowance();
+
+    const treasuryBefore = await balance(treasuryPDA);
+    await withFeeWaived(owner.publicKey, () =>
+      withdrawWithAllowance(PER_PERIOD, destination, feeOverrideAccounts(owner.publicKey))
+    );
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+  });
+
+  it("Stops the spender once the owner revokes", async () => {
+    await program.methods
+      .revokeAllowance()
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        allowance: allowancePDA(),
+        user: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    assert.isFalse(await exists(allowancePDA()));
+    await expectError(withdrawWithAllowance(1000), "AccountNotInitialized");
+  });
+});
@@ -0,0 +1,92 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  PLATFORM_FEE_BASIS_POINTS,
+  admin,
+  allStatsShards,
+  balance,
+  createUser,
+  expectError,
+  program,
+  rolesPDA,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+} from "./fixture";
+
+describe("batch deposit", () => {
+  const AMOUNTS = [0.2 * LAMPORTS_PER_SOL, 0.3 * LAMPORTS_PER_SOL];
+
+  let users: Keypair[];
+
+  const writable = (pubkey: anchor.web3.PublicKey) => ({ pubkey, isWritable: true, isSigner: false });
+
+  // `pairs` defaults to each user's own (user_config, vault)
+  const batchDeposit = (
+    amounts: number[],
+    pairs = users.map((user) => [
+      userAccounts(user.publicKey).userConfig,
+      userAccounts(user.publicKey).vault,
+    ])
+  ) =>
+    program.methods
+      .batchDeposit(amounts.map((amount) => new anchor.BN(amount)))
+      .accounts({
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        payer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([...allStatsShards.map(writable), ...pairs.flat().map(writable)])
+      .rpc();
+
+  const vaultBalances = () =>
+    Promise.all(users.map((user) => balance(userAccounts(user.publicKey).vault)));
+
+  before(async () => {
+    await setupProtocol();
+    users = [await createUser(), await createUser()];
+  });
+
+  it("Funds every vault less its fee and sends the fees in one transfer", async () => {
+    const vaultsBefore = await vaultBalances();
+    const treasuryBefore = await balance(treasuryPDA);
+
+    await batchDeposit(AMOUNTS);
+
+    const fees = AMOUNTS.map((amount) => (amount * PLATFORM_FEE_BASIS_POINTS) / 10_000);
+    const vaultsAfter = await vaultBalances();
+    vaultsAfter.forEach((after, i) => assert.equal(after - vaultsBefore[i], AMOUNTS[i] - fees[i]));
+    assert.equal((await balance(treasuryPDA)) - treasuryBefore, fees[0] + fees[1]);
+  });
+
+  it("Rejects batches whose accounts don't line up with the amounts", async () => {
+    await expectError(batchDeposit(AMOUNTS.slice(0, 1)), "InvalidBatch");
+
+    // One user's config paired with another user's vault
+    await expectError(
+      batchDeposit(AMOUNTS, [
+        [userAccounts(users[0].publicKey).userConfig, userAccounts(users[1].publicKey).vault],
+        [userAccounts(users[1].publicKey).userConfig, userAccounts(users[0].publicKey).vault],
+      ]),
+      "InvalidBatch"
+    );
+  });
+
+  it("Fails the whole batch when one user can't take deposits", async () => {
+    await program.methods
+      .freezeUser(Keypair.generate().publicKey)
+      .accounts({
+        roles: rolesPDA,
+        userConfig: userAccounts(users[1].publicKey).userConfig,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const vaultsBefore = await vaultBalances();
+    await expectError(batchDeposit(AMOUNTS), "AccountFrozen");
+    assert.deepEqual(await vaultBalances(), vaultsBefore);
+  });
+});
@@ -0,0 +1,135 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  admin,
+  balance,
+  createUser,
+  expectError,
+  feeOverrideAccounts,
+  pda,
+  program,
+  rolesPDA,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  withFeeWaived,
+} from "./fixture";
+
+describe("savings circle", () => {
+  const CIRCLE_ID = new anchor.BN(Date.now());
+  const COLLATERAL = 0.5 * LAMPORTS_PER_SOL;
+
+  let members: Keypair[];
+  let circlePDA: PublicKey;
+  let fundsPDA: PublicKey;
+
+  const memberAction = (
+    method: "joinCircle" | "leaveCircle" | "contributeToCircle",
+    member: Keypair
+  ) =>
+    program.methods[method]()
+      .accounts({
+        circle: circlePDA,
+        funds: fundsPDA,
+        treasuryConfig: treasuryConfigPDA,
+        memberConfig: userAccounts(member.publicKey).userConfig,
+        member: member.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([member])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    members = [await createUser(), await createUser()];
+    // Circle addresses are per creator, so nobody can take another creator's id
+    circlePDA = pda(
+      Buffer.from("savings_circle"),
+      members[0].publicKey.toBuffer(),
+      CIRCLE_ID.toArrayLike(Buffer, "le", 8)
+    );
+    fundsPDA = pda(Buffer.from("circle_funds"), circlePDA.toBuffer());
+
+    await program.methods
+      .createCircle(
+        CIRCLE_ID,
+        members.map((member) => member.publicKey),
+        new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+        new anchor.BN(COLLATERAL),
+        new anchor.BN(60),
+        100
+      )
+      .accounts({
+        circle: circlePDA,
+        funds: fundsPDA,
+        creator: members[0].publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([members[0]])
+      .rpc();
+  });
+
+  it("Returns collateral to a member who leaves before the circle fills", async () => {
+    await memberAction("joinCircle", members[0]);
+    assert.equal(await balance(fundsPDA), COLLATERAL);
+
+    await memberAction("leaveCircle", members[0]);
+    assert.equal(await balance(fundsPDA), 0);
+    const circle = await program.account.savingsCircle.fetch(circlePDA);
+    assert.isFalse(circle.members[0].joined);
+    assert.isTrue(circle.members[0].collateral.eqn(0));
+
+    // Leaving twice returns nothing more
+    await expectError(memberAction("leaveCircle", members[0]), "NotCircleMember");
+  });
+
+  it("Refuses to let a frozen member join", async () => {
+    const manageAccounts = {
+      roles: rolesPDA,
+      userConfig: userAccounts(members[1].publicKey).userConfig,
+      admin: admin.publicKey,
+    };
+    await program.methods.freezeUser(admin.publicKey).accounts(manageAccounts).rpc();
+    await expectError(memberAction("joinCircle", members[1]), "AccountFrozen");
+    await program.methods.unfreezeUser().accounts(manageAccounts).rpc();
+  });
+
+  it("Locks collateral in once every member has joined", async () => {
+    await memberAction("joinCircle", members[0]);
+    await memberAction("joinCircle", members[1]);
+    const circle = await program.account.savingsCircle.fetch(circlePDA);
+    assert.deepEqual(circle.status, { active: {} });
+
+    await expectError(memberAction("leaveCircle", members[0]), "InvalidCircleState");
+  });
+
+  it("Takes the round's fee at the recipient's negotiated rate", async () => {
+    for (const member of members) {
+      await memberAction("contributeToCircle", member);
+    }
+    const { pot } = await program.account.savingsCircle.fetch(circlePDA);
+
+    const treasuryBefore = await balance(treasuryPDA);
+    const recipientBefore = await balance(members[0].publicKey);
+    await withFeeWaived(members[0].publicKey, () =>
+      program.methods
+        .advanceCircleRound()
+        .accounts({
+          circle: circlePDA,
+          funds: fundsPDA,
+          treasuryConfig: treasuryConfigPDA,
+          treasury: treasuryPDA,
+          recipient: members[0].publicKey,
+          cranker: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...feeOverrideAccounts(members[0].publicKey),
+        })
+        .rpc()
+    );
+
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+    assert.equal(await balance(members[0].publicKey), recipientBefore + pot.toNumber());
+  });
+});
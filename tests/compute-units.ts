@@ -0,0 +1,189 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  admin,
+  allStatsShards,
+  createUser,
+  program,
+  provider,
+  rewardFundsPDA,
+  rewardPoolPDA,
+  setupProtocol,
+  setupSharePool,
+  shareMintPDA,
+  sharePoolPDA,
+  shareVaultPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  vaultAccounts,
+} from "./fixture";
+
+// Documented per-instruction budgets (see the doc comments in
+// programs/auto-savings/src/lib.rs). Measured against the default build,
+// i.e. without the `verbose-logs` feature.
+const CU_BUDGETS = {
+  deposit: 15_000,
+  withdraw: 15_000,
+  depositFor: 20_000,
+  batchDeposit: 40_000,
+  depositPooled: 20_000,
+  withdrawPooled: 20_000,
+  syncRewards: 10_000,
+  claimRewards: 20_000,
+  aggregateStats: 50_000,
+};
+
+describe("compute units", () => {
+  const report: Record<string, number> = {};
+  let others: Keypair[];
+
+  const unitsConsumed = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return tx.meta.computeUnitsConsumed;
+  };
+
+  // Send `method` and check what it used against its budget
+  const measure = async (
+    instruction: keyof typeof CU_BUDGETS,
+    method: { rpc: (options?: { commitment: "confirmed" }) => Promise<string> }
+  ) => {
+    const signature = await method.rpc({ commitment: "confirmed" });
+    report[instruction] = await unitsConsumed(signature);
+    assert.isAtMost(report[instruction], CU_BUDGETS[instruction]);
+  };
+
+  const pooledAccounts = {
+    userConfig: userAccounts(admin.publicKey).userConfig,
+    treasuryConfig: treasuryConfigPDA,
+    treasury: treasuryPDA,
+    sharePool: sharePoolPDA,
+    shareMint: shareMintPDA,
+    shareVault: shareVaultPDA,
+    statsShard: userAccounts(admin.publicKey).statsShard,
+    user: admin.publicKey,
+    systemProgram: SystemProgram.programId,
+  };
+
+  before(async () => {
+    await setupProtocol();
+    await setupSharePool();
+    others = [await createUser(), await createUser()];
+  });
+
+  after(() => {
+    console.table(
+      Object.entries(report).map(([instruction, units]) => ({
+        instruction,
+        units,
+        budget: CU_BUDGETS[instruction],
+      }))
+    );
+  });
+
+  it("Keeps deposit under budget", async () => {
+    await measure(
+      "deposit",
+      program.methods.deposit(new anchor.BN(0.5 * LAMPORTS_PER_SOL)).accounts({
+        ...vaultAccounts(admin.publicKey),
+        matchingProgram: null,
+        matchingFunds: null,
+        matchingParticipant: null,
+      })
+    );
+  });
+
+  it("Keeps withdraw under budget", async () => {
+    await measure(
+      "withdraw",
+      program.methods
+        .withdraw(new anchor.BN(0.1 * LAMPORTS_PER_SOL))
+        .accounts(vaultAccounts(admin.publicKey))
+    );
+  });
+
+  it("Keeps third-party deposits under budget", async () => {
+    const beneficiary = others[0].publicKey;
+    await measure(
+      "depositFor",
+      program.methods
+        .depositFor(beneficiary, new anchor.BN(0.1 * LAMPORTS_PER_SOL), null)
+        .accounts({
+          ...userAccounts(beneficiary),
+          treasuryConfig: treasuryConfigPDA,
+          treasury: treasuryPDA,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+    );
+
+    await measure(
+      "batchDeposit",
+      program.methods
+        .batchDeposit(others.map(() => new anchor.BN(0.1 * LAMPORTS_PER_SOL)))
+        .accounts({
+          treasuryConfig: treasuryConfigPDA,
+          treasury: treasuryPDA,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          ...allStatsShards.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })),
+          ...others.flatMap((user) => [
+            { pubkey: userAccounts(user.publicKey).userConfig, isWritable: true, isSigner: false },
+            { pubkey: userAccounts(user.publicKey).vault, isWritable: true, isSigner: false },
+          ]),
+        ])
+    );
+  });
+
+  it("Keeps pooled deposits and withdrawals under budget", async () => {
+    await measure(
+      "depositPooled",
+      program.methods.depositPooled(new anchor.BN(0.5 * LAMPORTS_PER_SOL)).accounts(pooledAccounts)
+    );
+
+    const { pooledShares } = await program.account.userConfig.fetch(pooledAccounts.userConfig);
+    await measure(
+      "withdrawPooled",
+      program.methods.withdrawPooled(pooledShares.divn(2)).accounts(pooledAccounts)
+    );
+  });
+
+  it("Keeps the reward and stats cranks under budget", async () => {
+    const { userConfig, vault, statsShard } = userAccounts(admin.publicKey);
+    await measure(
+      "syncRewards",
+      program.methods
+        .syncRewards()
+        .accounts({ userConfig, vault, statsShard, rewardPool: rewardPoolPDA })
+    );
+    await measure(
+      "claimRewards",
+      program.methods.claimRewards().accounts({
+        ...userAccounts(admin.publicKey),
+        rewardPool: rewardPoolPDA,
+        rewardFunds: rewardFundsPDA,
+        user: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+    );
+    await measure(
+      "aggregateStats",
+      program.methods
+        .aggregateStats()
+        .accounts({
+          treasuryConfig: treasuryConfigPDA,
+          sharePool: sharePoolPDA,
+          rewardPool: rewardPoolPDA,
+        })
+        .remainingAccounts(
+          allStatsShards.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }))
+        )
+    );
+  });
+});
@@ -0,0 +1,76 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  PLATFORM_FEE_BASIS_POINTS,
+  admin,
+  balance,
+  createUser,
+  expectError,
+  fundedKeypair,
+  program,
+  rolesPDA,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+} from "./fixture";
+
+describe("deposit for", () => {
+  const AMOUNT = 0.5 * LAMPORTS_PER_SOL;
+
+  let beneficiary: Keypair;
+  let payer: Keypair;
+
+  const depositFor = (lamports: number, memo: string | null = null) =>
+    program.methods
+      .depositFor(beneficiary.publicKey, new anchor.BN(lamports), memo)
+      .accounts({
+        ...userAccounts(beneficiary.publicKey),
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        payer: payer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([payer])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    beneficiary = await createUser();
+    payer = await fundedKeypair();
+  });
+
+  it("Credits the full amount with the payer covering the fee", async () => {
+    const vault = userAccounts(beneficiary.publicKey).vault;
+    const vaultBefore = await balance(vault);
+    const treasuryBefore = await balance(treasuryPDA);
+    const beneficiaryBefore = await balance(beneficiary.publicKey);
+
+    await depositFor(AMOUNT, "happy birthday");
+
+    assert.equal((await balance(vault)) - vaultBefore, AMOUNT);
+    assert.equal(
+      (await balance(treasuryPDA)) - treasuryBefore,
+      (AMOUNT * PLATFORM_FEE_BASIS_POINTS) / 10_000
+    );
+    assert.equal(await balance(beneficiary.publicKey), beneficiaryBefore);
+  });
+
+  it("Rejects memos over the limit", async () => {
+    await expectError(depositFor(AMOUNT, "x".repeat(65)), "MemoTooLong");
+  });
+
+  it("Counts against the beneficiary's own deposit limits", async () => {
+    await program.methods
+      .setUserLimits(null, new anchor.BN(AMOUNT))
+      .accounts({
+        roles: rolesPDA,
+        userConfig: userAccounts(beneficiary.publicKey).userConfig,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    await expectError(depositFor(AMOUNT), "UserLimitExceeded");
+  });
+});
@@ -0,0 +1,86 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  admin,
+  balance,
+  createUser,
+  expectError,
+  feeOverrideAccounts,
+  feeOverridePDA,
+  program,
+  removeFeeOverride,
+  setFeeOverride,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  vaultAccounts,
+} from "./fixture";
+
+describe("fee overrides", () => {
+  const partner = Keypair.generate().publicKey;
+  const { vault: vaultPDA } = userAccounts(admin.publicKey);
+
+  const deposit = (lamports: number, feeOverride: PublicKey) =>
+    program.methods
+      .deposit(new anchor.BN(lamports))
+      .accounts({
+        ...vaultAccounts(admin.publicKey),
+        matchingProgram: null,
+        matchingFunds: null,
+        matchingParticipant: null,
+        feeRate: { feeOverride, instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY },
+      })
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+  });
+
+  it("Waives the platform fee under a zero-bps user override", async () => {
+    await setFeeOverride(admin.publicKey, 0);
+
+    const treasuryBefore = await balance(treasuryPDA);
+    const vaultBefore = await balance(vaultPDA);
+    await deposit(0.1 * LAMPORTS_PER_SOL, feeOverridePDA(admin.publicKey));
+
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+    assert.equal(await balance(vaultPDA), vaultBefore + 0.1 * LAMPORTS_PER_SOL);
+
+    await removeFeeOverride(admin.publicKey);
+  });
+
+  it("Rejects a partner override outside that partner's program", async () => {
+    await setFeeOverride(partner, 0);
+    try {
+      await expectError(
+        deposit(0.1 * LAMPORTS_PER_SOL, feeOverridePDA(partner)),
+        "InvalidFeeOverride"
+      );
+    } finally {
+      await removeFeeOverride(partner);
+    }
+  });
+
+  it("Applies the payer's override to deposits made for someone else", async () => {
+    const beneficiary = await createUser();
+    await setFeeOverride(admin.publicKey, 0);
+
+    const treasuryBefore = await balance(treasuryPDA);
+    await program.methods
+      .depositFor(beneficiary.publicKey, new anchor.BN(0.1 * LAMPORTS_PER_SOL), null)
+      .accounts({
+        ...userAccounts(beneficiary.publicKey),
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        payer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...feeOverrideAccounts(admin.publicKey),
+      })
+      .rpc();
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+
+    await removeFeeOverride(admin.publicKey);
+  });
+});
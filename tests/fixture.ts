@@ -0,0 +1,324 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  LAMPORTS_PER_SOL,
+  SYSVAR_CLOCK_PUBKEY,
+} from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { AutoSavings } from "../target/types/auto_savings";
+import { assert } from "chai";
+
+// Shared setup for every test file. Mocha runs the files against one validator, so
+// anything protocol-wide (fee, pause mask, TVL cap) is put back to its default by
+// `setupProtocol` rather than trusting whatever the previous file left behind.
+
+// Mirrors the constants in programs/auto-savings/src/lib.rs
+export const STATS_SHARD_COUNT = 8;
+export const PLATFORM_FEE_BASIS_POINTS = 40;
+export const TVL_CAP_LAMPORTS = 100 * LAMPORTS_PER_SOL;
+export const PAUSE_DEPOSITS = 1 << 0;
+export const PAUSE_WITHDRAWALS = 1 << 1;
+export const PAUSE_NEW_USERS = 1 << 2;
+export const PAUSE_SWAPS = 1 << 3;
+export const PAUSE_SCHEDULES = 1 << 4;
+export const MAX_WITHDRAWAL_PAUSE_SECONDS = 72 * 60 * 60;
+export const ROLE_PAUSER = 1 << 0;
+export const ROLE_RISK_MANAGER = 1 << 1;
+export const ROLE_TREASURER = 1 << 2;
+export const ALL_ROLES = ROLE_PAUSER | ROLE_RISK_MANAGER | ROLE_TREASURER;
+
+export const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+export const program = anchor.workspace.AutoSavings as Program<AutoSavings>;
+export const admin = provider.wallet;
+
+export const pda = (...seeds: Buffer[]) =>
+  PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+export const treasuryConfigPDA = pda(Buffer.from("treasury_config"));
+export const treasuryPDA = pda(Buffer.from("treasury_vault"));
+export const rolesPDA = pda(Buffer.from("roles"));
+export const rewardPoolPDA = pda(Buffer.from("reward_pool"));
+export const rewardFundsPDA = pda(Buffer.from("reward_funds"));
+export const sharePoolPDA = pda(Buffer.from("share_pool"));
+export const shareMintPDA = pda(Buffer.from("share_mint"));
+export const shareVaultPDA = pda(Buffer.from("share_vault"));
+
+export const statsShardPDA = (index: number) =>
+  pda(Buffer.from("stats_shard"), Buffer.from([index]));
+export const allStatsShards = Array.from({ length: STATS_SHARD_COUNT }, (_, i) =>
+  statsShardPDA(i)
+);
+
+// Per-user PDAs, with the stats shard the owner's key hashes to
+export const userAccounts = (owner: PublicKey) => ({
+  userConfig: pda(Buffer.from("user_config"), owner.toBuffer()),
+  vault: pda(Buffer.from("vault"), owner.toBuffer()),
+  statsShard: statsShardPDA(owner.toBuffer()[0] % STATS_SHARD_COUNT),
+});
+
+export const exists = async (address: PublicKey) =>
+  (await provider.connection.getAccountInfo(address)) !== null;
+
+export const balance = (address: PublicKey) => provider.connection.getBalance(address);
+
+// Wait on the validator's Clock sysvar rather than the wall clock, so the test
+// only proceeds once the program itself sees `timestamp` as passed
+export const waitForClusterTime = async (timestamp: number) => {
+  for (;;) {
+    const clock = await provider.connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY, "processed");
+    const unixTimestamp = new anchor.BN(clock.data.subarray(32, 40), "le").toNumber();
+    if (unixTimestamp >= timestamp) {
+      return;
+    }
+    await provider.connection.getLatestBlockhash("processed");
+  }
+};
+
+// Assert that `promise` fails with the named program error
+export const expectError = async (promise: Promise<unknown>, code: string) => {
+  try {
+    await promise;
+  } catch (error) {
+    assert.include(error.toString(), code);
+    return;
+  }
+  assert.fail(`Expected ${code}`);
+};
+
+export const fundedKeypair = async (lamports = 10 * LAMPORTS_PER_SOL) => {
+  const keypair = Keypair.generate();
+  const signature = await provider.connection.requestAirdrop(keypair.publicKey, lamports);
+  const { blockhash, lastValidBlockHeight } = await provider.connection.getLatestBlockhash();
+  await provider.connection.confirmTransaction({ signature, blockhash, lastValidBlockHeight });
+  return keypair;
+};
+
+// Open a vault for `user` (the provider wallet when omitted)
+export const initializeUser = (user?: Keypair) => {
+  const owner = user ? user.publicKey : admin.publicKey;
+  const { userConfig, vault } = userAccounts(owner);
+  return program.methods
+    .initializeUser([])
+    .accounts({
+      treasuryConfig: treasuryConfigPDA,
+      userConfig,
+      vault,
+      verifier: null,
+      user: owner,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers(user ? [user] : [])
+    .rpc();
+};
+
+// A fresh funded wallet with an open vault
+export const createUser = async (lamports = 10 * LAMPORTS_PER_SOL) => {
+  const user = await fundedKeypair(lamports);
+  await initializeUser(user);
+  return user;
+};
+
+// Accounts shared by `deposit` and `withdraw`, with every optional account left out
+export const vaultAccounts = (owner: PublicKey) => ({
+  ...userAccounts(owner),
+  treasuryConfig: treasuryConfigPDA,
+  treasury: treasuryPDA,
+  user: owner,
+  owner,
+  systemProgram: SystemProgram.programId,
+  feeRate: { feeOverride: null, instructions: null },
+});
+
+export const deposit = (lamports: number, user?: Keypair) =>
+  program.methods
+    .deposit(new anchor.BN(lamports))
+    .accounts({
+      ...vaultAccounts(user ? user.publicKey : admin.publicKey),
+      matchingProgram: null,
+      matchingFunds: null,
+      matchingParticipant: null,
+    })
+    .signers(user ? [user] : [])
+    .rpc();
+
+export const withdraw = (lamports: number, user?: Keypair) =>
+  program.methods
+    .withdraw(new anchor.BN(lamports))
+    .accounts(vaultAccounts(user ? user.publicKey : admin.publicKey))
+    .signers(user ? [user] : [])
+    .rpc();
+
+const roleGatedAccounts = () => ({
+  roles: rolesPDA,
+  treasuryConfig: treasuryConfigPDA,
+  admin: admin.publicKey,
+});
+
+export const setPauseMask = (mask: number, withdrawalPauseSeconds = 0) =>
+  program.methods
+    .setPauseMask(mask, new anchor.BN(withdrawalPauseSeconds))
+    .accounts(roleGatedAccounts())
+    .rpc();
+
+export const updateFee = (feeBasisPoints: number) =>
+  program.methods.updateFee(feeBasisPoints).accounts(roleGatedAccounts()).rpc();
+
+export const updateTvlCap = (lamports: number) =>
+  program.methods.updateTvlCap(new anchor.BN(lamports)).accounts(roleGatedAccounts()).rpc();
+
+export const setOnboardingGate = (verifier: PublicKey | null, allowlistRoot: number[] | null) =>
+  program.methods
+    .setOnboardingGate(verifier, allowlistRoot)
+    .accounts(roleGatedAccounts())
+    .rpc();
+
+export const feeOverridePDA = (subject: PublicKey) =>
+  pda(Buffer.from("fee_override"), subject.toBuffer());
+
+export const setFeeOverride = (subject: PublicKey, feeBasisPoints: number) =>
+  program.methods
+    .setFeeOverride(subject, feeBasisPoints)
+    .accounts({
+      roles: rolesPDA,
+      feeOverride: feeOverridePDA(subject),
+      admin: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+
+export const removeFeeOverride = (subject: PublicKey) =>
+  program.methods
+    .removeFeeOverride()
+    .accounts({
+      roles: rolesPDA,
+      feeOverride: feeOverridePDA(subject),
+      admin: admin.publicKey,
+    })
+    .rpc();
+
+// The optional accounts that apply `subject`'s fee override
+export const feeOverrideAccounts = (subject: PublicKey) => ({
+  feeRate: {
+    feeOverride: feeOverridePDA(subject),
+    instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+  },
+});
+
+// Run `action` with `subject` on a zero-bps override, removing it again afterwards
+export const withFeeWaived = async (subject: PublicKey, action: () => Promise<unknown>) => {
+  await setFeeOverride(subject, 0);
+  try {
+    await action();
+  } finally {
+    await removeFeeOverride(subject);
+  }
+};
+
+// Crank the TVL total, hand every shard (and the pool, once it exists) fresh quota
+// and settle every shard's rewards
+export const aggregateStats = () =>
+  program.methods
+    .aggregateStats()
+    .accounts({
+      treasuryConfig: treasuryConfigPDA,
+      sharePool: sharePoolPDA,
+      rewardPool: rewardPoolPDA,
+    })
+    .remainingAccounts(
+      allStatsShards.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }))
+    )
+    .rpc();
+
+// Put the protocol-wide knobs other files may have turned back to their defaults
+export const resetGlobalState = async () => {
+  await setPauseMask(0);
+  await updateFee(PLATFORM_FEE_BASIS_POINTS);
+  await updateTvlCap(TVL_CAP_LAMPORTS);
+  await aggregateStats();
+  await setOnboardingGate(null, null);
+};
+
+// Create whichever protocol accounts no earlier file has, open the provider
+// wallet's vault, and reset global state
+export const setupProtocol = async () => {
+  if (!(await exists(treasuryConfigPDA))) {
+    await program.methods
+      .initializeTreasury()
+      .accounts({
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        authority: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  if (!(await exists(rolesPDA))) {
+    await program.methods
+      .initializeRoles()
+      .accounts({
+        roles: rolesPDA,
+        treasuryConfig: treasuryConfigPDA,
+        payer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  if (!(await exists(rewardPoolPDA))) {
+    await program.methods
+      .initializeRewardPool()
+      .accounts({
+        roles: rolesPDA,
+        rewardPool: rewardPoolPDA,
+        rewardFunds: rewardFundsPDA,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  for (let index = 0; index < STATS_SHARD_COUNT; index++) {
+    if (!(await exists(statsShardPDA(index)))) {
+      await program.methods
+        .initializeStatsShard(index)
+        .accounts({
+          statsShard: statsShardPDA(index),
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  }
+
+  if (!(await exists(userAccounts(admin.publicKey).userConfig))) {
+    await initializeUser();
+  }
+
+  await resetGlobalState();
+};
+
+// Create the share pool if no earlier file has, and crank it its first quota
+export const setupSharePool = async () => {
+  if (await exists(sharePoolPDA)) {
+    return;
+  }
+  await program.methods
+    .initializeSharePool()
+    .accounts({
+      roles: rolesPDA,
+      sharePool: sharePoolPDA,
+      shareMint: shareMintPDA,
+      shareVault: shareVaultPDA,
+      admin: admin.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  await aggregateStats();
+};
@@ -0,0 +1,349 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import {
+  PLATFORM_FEE_BASIS_POINTS,
+  admin,
+  balance,
+  createUser,
+  deposit,
+  expectError,
+  feeOverrideAccounts,
+  fundedKeypair,
+  pda,
+  program,
+  provider,
+  rewardFundsPDA,
+  rewardPoolPDA,
+  rolesPDA,
+  setupProtocol,
+  setupSharePool,
+  shareMintPDA,
+  sharePoolPDA,
+  shareVaultPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  withFeeWaived,
+  withdraw,
+} from "./fixture";
+
+describe("freeze", () => {
+  const TOKENS = 1_000_000;
+
+  let user: Keypair;
+  let mint: PublicKey;
+  const recoveryDestination = Keypair.generate().publicKey;
+
+  const tokenVaultPDA = () =>
+    pda(Buffer.from("token_vault"), user.publicKey.toBuffer(), mint.toBuffer());
+
+  const manageUser = (method: "freezeUser" | "unfreezeUser", signer?: Keypair) =>
+    (method === "freezeUser"
+      ? program.methods.freezeUser(recoveryDestination)
+      : program.methods.unfreezeUser()
+    )
+      .accounts({
+        roles: rolesPDA,
+        userConfig: userAccounts(user.publicKey).userConfig,
+        admin: signer ? signer.publicKey : admin.publicKey,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+
+  const withdrawFrozen = (
+    lamports: number,
+    authority: Keypair,
+    destination: PublicKey = recoveryDestination,
+    feeOverride: object = {}
+  ) =>
+    program.methods
+      .withdrawFrozen(new anchor.BN(lamports))
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        vault: userAccounts(user.publicKey).vault,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        statsShard: userAccounts(user.publicKey).statsShard,
+        recoveryDestination: destination,
+        sharePool: null,
+        shareMint: null,
+        shareVault: null,
+        roles: rolesPDA,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...feeOverride,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser();
+    await deposit(LAMPORTS_PER_SOL, user);
+
+    mint = await createMint(provider.connection, admin.payer, admin.publicKey, null, 6);
+    await program.methods
+      .initializeTokenVault()
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        mint,
+        tokenVault: tokenVaultPDA(),
+        user: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    await mintTo(provider.connection, admin.payer, mint, tokenVaultPDA(), admin.payer, TOKENS);
+  });
+
+  it("Only lets risk managers freeze a vault", async () => {
+    await expectError(manageUser("freezeUser", await fundedKeypair()), "MissingRole");
+    await manageUser("freezeUser");
+
+    const userConfig = await program.account.userConfig.fetch(userAccounts(user.publicKey).userConfig);
+    assert.isTrue(userConfig.recoveryDestination.equals(recoveryDestination));
+  });
+
+  it("Blocks the frozen owner's deposits and withdrawals", async () => {
+    await expectError(deposit(1000, user), "AccountFrozen");
+    await expectError(withdraw(1000, user), "AccountFrozen");
+  });
+
+  it("Blocks the frozen owner's reward claims", async () => {
+    await expectError(
+      program.methods
+        .claimRewards()
+        .accounts({
+          ...userAccounts(user.publicKey),
+          rewardPool: rewardPoolPDA,
+          rewardFunds: rewardFundsPDA,
+          user: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc(),
+      "AccountFrozen"
+    );
+  });
+
+  it("Sends frozen withdrawals only to the recovery destination", async () => {
+    await expectError(withdrawFrozen(1000, user, user.publicKey), "InvalidDestination");
+
+    const amount = 0.25 * LAMPORTS_PER_SOL;
+    await withdrawFrozen(amount, user);
+    assert.equal(await balance(recoveryDestination), amount);
+  });
+
+  it("Lets a risk manager push frozen lamports to the recovery destination", async () => {
+    const before = await balance(recoveryDestination);
+    const amount = 0.25 * LAMPORTS_PER_SOL;
+    await withdrawFrozen(amount, admin.payer);
+    assert.equal(await balance(recoveryDestination), before + amount);
+
+    // Nobody else can, even though the funds could only go to the recovery destination
+    await expectError(withdrawFrozen(1000, await fundedKeypair()), "Unauthorized");
+  });
+
+  it("Pushes frozen token vault balances to the recovery destination", async () => {
+    const recoveryTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      admin.payer,
+      mint,
+      recoveryDestination
+    );
+    const amount = TOKENS / 2;
+    await program.methods
+      .withdrawFrozenTokens(new anchor.BN(amount))
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        mint,
+        tokenVault: tokenVaultPDA(),
+        recoveryTokenAccount,
+        treasury: treasuryPDA,
+        treasuryTokenAccount: pda(Buffer.from("treasury_token"), mint.toBuffer()),
+        roles: rolesPDA,
+        authority: admin.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const recovered = await getAccount(provider.connection, recoveryTokenAccount);
+    assert.equal(recovered.amount.toString(), amount.toString());
+    const vault = await getAccount(provider.connection, tokenVaultPDA());
+    const fee = (amount * PLATFORM_FEE_BASIS_POINTS) / 10_000;
+    assert.equal(vault.amount.toString(), (TOKENS - amount - fee).toString());
+  });
+
+  it("Charges frozen withdrawals at the owner's negotiated rate", async () => {
+    const treasuryBefore = await balance(treasuryPDA);
+    const recoveredBefore = await balance(recoveryDestination);
+    const amount = 0.1 * LAMPORTS_PER_SOL;
+    await withFeeWaived(user.publicKey, () =>
+      withdrawFrozen(
+        amount,
+        admin.payer,
+        recoveryDestination,
+        feeOverrideAccounts(user.publicKey)
+      )
+    );
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+    assert.equal(await balance(recoveryDestination), recoveredBefore + amount);
+
+    const treasuryTokenAccount = pda(Buffer.from("treasury_token"), mint.toBuffer());
+    const feesBefore = (await getAccount(provider.connection, treasuryTokenAccount)).amount;
+    const vaultBefore = (await getAccount(provider.connection, tokenVaultPDA())).amount;
+    const tokens = 1000;
+    await withFeeWaived(user.publicKey, () =>
+      program.methods
+        .withdrawFrozenTokens(new anchor.BN(tokens))
+        .accounts({
+          userConfig: userAccounts(user.publicKey).userConfig,
+          treasuryConfig: treasuryConfigPDA,
+          mint,
+          tokenVault: tokenVaultPDA(),
+          recoveryTokenAccount: getAssociatedTokenAddressSync(mint, recoveryDestination),
+          treasury: treasuryPDA,
+          treasuryTokenAccount,
+          roles: rolesPDA,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          ...feeOverrideAccounts(user.publicKey),
+        })
+        .rpc()
+    );
+    assert.equal((await getAccount(provider.connection, treasuryTokenAccount)).amount, feesBefore);
+    assert.equal(
+      (await getAccount(provider.connection, tokenVaultPDA())).amount,
+      vaultBefore - BigInt(tokens)
+    );
+  });
+
+  it("Restores normal withdrawals once unfrozen", async () => {
+    await manageUser("unfreezeUser");
+
+    const userConfig = await program.account.userConfig.fetch(userAccounts(user.publicKey).userConfig);
+    assert.isTrue(userConfig.recoveryDestination.equals(PublicKey.default));
+    await withdraw(1000, user);
+  });
+
+  it("Sweeps a frozen pooled position to the recovery destination", async () => {
+    await setupSharePool();
+    const saver = await createUser();
+    const { userConfig, vault, statsShard } = userAccounts(saver.publicKey);
+    await program.methods
+      .depositPooled(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({
+        userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        shareVault: shareVaultPDA,
+        statsShard,
+        user: saver.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([saver])
+      .rpc();
+
+    const destination = Keypair.generate().publicKey;
+    await program.methods
+      .freezeUser(destination)
+      .accounts({ roles: rolesPDA, userConfig, admin: admin.publicKey })
+      .rpc();
+    const frozenAccounts = {
+      userConfig,
+      vault,
+      treasuryConfig: treasuryConfigPDA,
+      treasury: treasuryPDA,
+      statsShard,
+      recoveryDestination: destination,
+      roles: rolesPDA,
+      authority: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+
+    // The pooled position can't be left behind
+    await expectError(
+      program.methods
+        .withdrawFrozen(new anchor.BN(0))
+        .accounts({ ...frozenAccounts, sharePool: null, shareMint: null, shareVault: null })
+        .rpc(),
+      "SharePoolAccountsRequired"
+    );
+    await program.methods
+      .withdrawFrozen(new anchor.BN(0))
+      .accounts({
+        ...frozenAccounts,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        shareVault: shareVaultPDA,
+      })
+      .rpc();
+
+    assert.isTrue((await program.account.userConfig.fetch(userConfig)).pooledShares.eqn(0));
+    assert.isAbove(await balance(destination), 0);
+  });
+
+  it("Sweeps a frozen pooled position at the owner's negotiated rate", async () => {
+    const saver = await createUser();
+    const { userConfig, vault, statsShard } = userAccounts(saver.publicKey);
+    await program.methods
+      .depositPooled(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({
+        userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        shareVault: shareVaultPDA,
+        statsShard,
+        user: saver.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([saver])
+      .rpc();
+    await program.methods
+      .freezeUser(recoveryDestination)
+      .accounts({ roles: rolesPDA, userConfig, admin: admin.publicKey })
+      .rpc();
+
+    const treasuryBefore = await balance(treasuryPDA);
+    await withFeeWaived(saver.publicKey, () =>
+      program.methods
+        .withdrawFrozen(new anchor.BN(0))
+        .accounts({
+          userConfig,
+          vault,
+          treasuryConfig: treasuryConfigPDA,
+          treasury: treasuryPDA,
+          statsShard,
+          recoveryDestination,
+          sharePool: sharePoolPDA,
+          shareMint: shareMintPDA,
+          shareVault: shareVaultPDA,
+          roles: rolesPDA,
+          authority: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...feeOverrideAccounts(saver.publicKey),
+        })
+        .rpc()
+    );
+
+    assert.isTrue((await program.account.userConfig.fetch(userConfig)).pooledShares.eqn(0));
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+  });
+});
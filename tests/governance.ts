@@ -0,0 +1,252 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  ALL_ROLES,
+  PAUSE_DEPOSITS,
+  PAUSE_SCHEDULES,
+  PLATFORM_FEE_BASIS_POINTS,
+  ROLE_PAUSER,
+  admin,
+  balance,
+  deposit,
+  expectError,
+  fundedKeypair,
+  pda,
+  program,
+  rolesPDA,
+  setPauseMask,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+} from "./fixture";
+
+// Handing authority to the council is one-way, so this file leaves the provider
+// wallet holding every role through a council grant for the files that run after it.
+describe("governance", () => {
+  const councilPDA = pda(Buffer.from("admin_council"));
+  const proposalPDA = (id: anchor.BN) =>
+    pda(Buffer.from("admin_proposal"), id.toArrayLike(Buffer, "le", 8));
+
+  let councilMember: Keypair;
+  let pauser: Keypair;
+
+  const setRoles = (member: PublicKey, roleFlags: number, superAdmin?: Keypair) =>
+    program.methods
+      .setRoles(member, roleFlags)
+      .accounts({
+        roles: rolesPDA,
+        superAdmin: superAdmin ? superAdmin.publicKey : admin.publicKey,
+      })
+      .signers(superAdmin ? [superAdmin] : [])
+      .rpc();
+
+  const withdrawTreasury = (lamports: number, treasurer?: Keypair) =>
+    program.methods
+      .withdrawTreasury(new anchor.BN(lamports))
+      .accounts({
+        roles: rolesPDA,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        destination: admin.publicKey,
+        admin: treasurer ? treasurer.publicKey : admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(treasurer ? [treasurer] : [])
+      .rpc();
+
+  const roleGated = (signer: Keypair) => ({
+    roles: rolesPDA,
+    treasuryConfig: treasuryConfigPDA,
+    admin: signer.publicKey,
+  });
+
+  const initializeCouncil = (members: PublicKey[], threshold: number) =>
+    program.methods
+      .initializeAdminCouncil(members, threshold, new anchor.BN(0))
+      .accounts({
+        adminCouncil: councilPDA,
+        treasuryConfig: treasuryConfigPDA,
+        roles: rolesPDA,
+        authority: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+  // Propose as the provider wallet; returns the proposal
+  const propose = async (action: object) => {
+    const { proposalCount } = await program.account.adminCouncil.fetch(councilPDA);
+    const proposal = proposalPDA(proposalCount);
+    await program.methods
+      .proposeAdminAction(action as never)
+      .accounts({
+        adminCouncil: councilPDA,
+        proposal,
+        proposer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    return proposal;
+  };
+
+  const approve = (proposal: PublicKey, approver = councilMember) =>
+    program.methods
+      .approveAdminAction()
+      .accounts({ adminCouncil: councilPDA, proposal, approver: approver.publicKey })
+      .signers([approver])
+      .rpc();
+
+  // Propose as the provider wallet, approve as the second member; returns the proposal
+  const proposeAndApprove = async (action: object, approver = councilMember) => {
+    const proposal = await propose(action);
+    await approve(proposal, approver);
+    return proposal;
+  };
+
+  const executeProposal = (proposal: PublicKey) =>
+    program.methods
+      .executeAdminAction()
+      .accounts({
+        adminCouncil: councilPDA,
+        proposal,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        destination: admin.publicKey,
+        roles: rolesPDA,
+        executor: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    councilMember = await fundedKeypair();
+    pauser = await fundedKeypair();
+    // Leave platform fees in the treasury for the withdrawal proposal
+    await deposit(LAMPORTS_PER_SOL);
+  });
+
+  it("Limits each role to its own instructions", async () => {
+    await setRoles(pauser.publicKey, ROLE_PAUSER);
+
+    await program.methods
+      .setPauseMask(PAUSE_SCHEDULES, new anchor.BN(0))
+      .accounts(roleGated(pauser))
+      .signers([pauser])
+      .rpc();
+    await expectError(
+      program.methods.updateFee(0).accounts(roleGated(pauser)).signers([pauser]).rpc(),
+      "MissingRole"
+    );
+    await expectError(withdrawTreasury(1, pauser), "MissingRole");
+    await expectError(setRoles(pauser.publicKey, ALL_ROLES, pauser), "Unauthorized");
+
+    await setPauseMask(0);
+  });
+
+  it("Rejects malformed councils", async () => {
+    const members = [admin.publicKey, councilMember.publicKey];
+    await expectError(initializeCouncil(members, 0), "InvalidCouncil");
+    await expectError(initializeCouncil(members, 3), "InvalidCouncil");
+    await expectError(initializeCouncil([admin.publicKey, admin.publicKey], 1), "InvalidCouncil");
+  });
+
+  it("Makes the council super admin and revokes earlier grants", async () => {
+    await initializeCouncil([admin.publicKey, councilMember.publicKey], 2);
+
+    const roles = await program.account.roles.fetch(rolesPDA);
+    assert.isTrue(roles.superAdmin.equals(councilPDA));
+    assert.lengthOf(roles.members, 0);
+
+    await expectError(setRoles(admin.publicKey, ALL_ROLES), "Unauthorized");
+    await expectError(setPauseMask(0), "MissingRole");
+  });
+
+  it("Grants roles only through approved council proposals", async () => {
+    const proposal = await proposeAndApprove({
+      setRoles: { member: admin.publicKey, roleFlags: ALL_ROLES },
+    });
+    await executeProposal(proposal);
+
+    await setPauseMask(0);
+    // Treasurer role alone no longer moves treasury funds
+    await expectError(withdrawTreasury(1), "CouncilGoverned");
+  });
+
+  it("Moves treasury funds once enough members approve", async () => {
+    const outsider = await fundedKeypair();
+    const proposal = await propose({
+      withdrawTreasury: { amount: new anchor.BN(1), destination: admin.publicKey },
+    });
+
+    await expectError(executeProposal(proposal), "ThresholdNotMet");
+    await expectError(approve(proposal, outsider), "Unauthorized");
+    await expectError(
+      program.methods
+        .approveAdminAction()
+        .accounts({ adminCouncil: councilPDA, proposal, approver: admin.publicKey })
+        .rpc(),
+      "AlreadyApproved"
+    );
+
+    await approve(proposal);
+    const before = await balance(treasuryPDA);
+    await executeProposal(proposal);
+    assert.equal(before - (await balance(treasuryPDA)), 1);
+    await expectError(executeProposal(proposal), "ProposalAlreadyExecuted");
+  });
+
+  it("Rotates council members and threshold through a proposal", async () => {
+    const replacement = await fundedKeypair();
+    const pending = await proposeAndApprove({
+      setFeeBasisPoints: { feeBasisPoints: PLATFORM_FEE_BASIS_POINTS },
+    });
+    await executeProposal(
+      await proposeAndApprove({
+        setCouncil: {
+          members: [admin.publicKey, replacement.publicKey],
+          threshold: 2,
+          timelockSeconds: new anchor.BN(0),
+        },
+      })
+    );
+
+    const council = await program.account.adminCouncil.fetch(councilPDA);
+    assert.isTrue(council.members[1].equals(replacement.publicKey));
+
+    // The removed member can no longer approve, and their earlier approval stops counting
+    await expectError(
+      proposeAndApprove({ setFeeBasisPoints: { feeBasisPoints: 40 } }),
+      "Unauthorized"
+    );
+    await expectError(executeProposal(pending), "ThresholdNotMet");
+    await approve(pending, replacement);
+    await executeProposal(pending);
+    councilMember = replacement;
+  });
+
+  it("Holds ordinary actions for the timelock but lets emergency pauses through", async () => {
+    await executeProposal(
+      await proposeAndApprove({
+        setCouncil: {
+          members: [admin.publicKey, councilMember.publicKey],
+          threshold: 2,
+          timelockSeconds: new anchor.BN(3600),
+        },
+      })
+    );
+
+    const feeChange = await proposeAndApprove({ setFeeBasisPoints: { feeBasisPoints: 40 } });
+    await expectError(executeProposal(feeChange), "TimelockNotElapsed");
+
+    await executeProposal(
+      await proposeAndApprove({
+        setPauseMask: { pauseMask: PAUSE_DEPOSITS, withdrawalPauseSeconds: new anchor.BN(0) },
+      })
+    );
+    const config = await program.account.treasuryConfig.fetch(treasuryConfigPDA);
+    assert.equal(config.pauseMask, PAUSE_DEPOSITS);
+
+    await setPauseMask(0);
+  });
+});
@@ -0,0 +1,45 @@
+import { Keypair, SystemProgram } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createMint } from "@solana/spl-token";
+import { assert } from "chai";
+import {
+  admin,
+  createUser,
+  pda,
+  program,
+  provider,
+  setupProtocol,
+  userAccounts,
+  waitForClusterTime,
+} from "./fixture";
+
+describe("inheritance", () => {
+  let owner: Keypair;
+
+  before(async () => {
+    await setupProtocol();
+    owner = await createUser();
+  });
+
+  it("Resets the inactivity timer when the owner opens a token vault", async () => {
+    const { userConfig } = userAccounts(owner.publicKey);
+    const before = (await program.account.userConfig.fetch(userConfig)).lastActivityTs.toNumber();
+    await waitForClusterTime(before + 2);
+
+    const mint = await createMint(provider.connection, admin.payer, admin.publicKey, null, 6);
+    await program.methods
+      .initializeTokenVault()
+      .accounts({
+        userConfig,
+        mint,
+        tokenVault: pda(Buffer.from("token_vault"), owner.publicKey.toBuffer(), mint.toBuffer()),
+        user: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const after = (await program.account.userConfig.fetch(userConfig)).lastActivityTs.toNumber();
+    assert.isAbove(after, before);
+  });
+});
@@ -0,0 +1,261 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  PLATFORM_FEE_BASIS_POINTS,
+  STATS_SHARD_COUNT,
+  balance,
+  createUser,
+  expectError,
+  feeOverrideAccounts,
+  fundedKeypair,
+  pda,
+  program,
+  setupProtocol,
+  statsShardPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  withFeeWaived,
+} from "./fixture";
+
+describe("joint vault", () => {
+  const VAULT_ID = new anchor.BN(1);
+
+  let creator: Keypair;
+  let partner: Keypair;
+
+  const jointVaultPDA = (owner: PublicKey, vaultId = VAULT_ID) =>
+    pda(Buffer.from("joint_vault"), owner.toBuffer(), vaultId.toArrayLike(Buffer, "le", 8));
+  const fundsPDA = (jointVault: PublicKey) =>
+    pda(Buffer.from("joint_vault_funds"), jointVault.toBuffer());
+
+  const createJointVault = (
+    signer: Keypair,
+    members: PublicKey[],
+    vaultId = VAULT_ID,
+    policy: object = { proportional: {} }
+  ) => {
+    const jointVault = jointVaultPDA(signer.publicKey, vaultId);
+    return program.methods
+      .createJointVault(vaultId, members, policy as never)
+      .accounts({
+        jointVault,
+        funds: fundsPDA(jointVault),
+        creator: signer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([signer])
+      .rpc();
+  };
+
+  const jointAccounts = (member: Keypair, jointVault = jointVaultPDA(creator.publicKey)) => ({
+    jointVault,
+    funds: fundsPDA(jointVault),
+    memberConfig: userAccounts(member.publicKey).userConfig,
+    treasuryConfig: treasuryConfigPDA,
+    statsShard: statsShardPDA(jointVault.toBuffer()[0] % STATS_SHARD_COUNT),
+    treasury: treasuryPDA,
+    member: member.publicKey,
+    systemProgram: SystemProgram.programId,
+  });
+
+  const jointDeposit = (
+    member: Keypair,
+    lamports: number,
+    jointVault = jointVaultPDA(creator.publicKey),
+    feeOverride: object = {}
+  ) =>
+    program.methods
+      .jointDeposit(new anchor.BN(lamports))
+      .accounts({ ...jointAccounts(member, jointVault), ...feeOverride })
+      .signers([member])
+      .rpc();
+
+  const jointWithdraw = (
+    member: Keypair,
+    lamports: anchor.BN,
+    jointVault = jointVaultPDA(creator.publicKey),
+    feeOverride: object = {}
+  ) =>
+    program.methods
+      .jointWithdraw(lamports)
+      .accounts({ ...jointAccounts(member, jointVault), ...feeOverride })
+      .signers([member])
+      .rpc();
+
+  const memberEntry = async (member: Keypair) => {
+    const { members } = await program.account.jointVault.fetch(jointVaultPDA(creator.publicKey));
+    return members.find((entry) => entry.key.equals(member.publicKey));
+  };
+
+  before(async () => {
+    await setupProtocol();
+    creator = await createUser();
+    partner = await createUser();
+    await createJointVault(creator, [creator.publicKey, partner.publicKey]);
+  });
+
+  it("Rejects vaults the creator is not in or with a threshold above the member count", async () => {
+    const outsider = await fundedKeypair();
+    await expectError(
+      createJointVault(outsider, [creator.publicKey, partner.publicKey]),
+      "InvalidJointVault"
+    );
+    await expectError(
+      createJointVault(creator, [creator.publicKey, partner.publicKey], new anchor.BN(3), {
+        threshold: { threshold: 3 },
+      }),
+      "InvalidJointVault"
+    );
+  });
+
+  it("Scopes vault ids to their creator", async () => {
+    // The same id under another creator is a different vault
+    await createJointVault(partner, [partner.publicKey, creator.publicKey]);
+    const vault = await program.account.jointVault.fetch(jointVaultPDA(partner.publicKey));
+    assert.isTrue(vault.creator.equals(partner.publicKey));
+  });
+
+  it("Charges proportional withdrawals, fee included, against the member's contribution", async () => {
+    await jointDeposit(creator, LAMPORTS_PER_SOL);
+    await jointDeposit(partner, LAMPORTS_PER_SOL / 2);
+    await expectError(jointDeposit(await createUser(), 1000), "NotJointMember");
+    const { contributed } = await memberEntry(creator);
+
+    // The whole contribution plus its fee is more than the member put in
+    await expectError(jointWithdraw(creator, contributed), "ExceedsContribution");
+
+    const amount = contributed.divn(2);
+    await jointWithdraw(creator, amount);
+    const fee = amount.muln(PLATFORM_FEE_BASIS_POINTS).divn(10_000);
+    assert.isTrue((await memberEntry(creator)).withdrawn.eq(amount.add(fee)));
+
+    // What is left is less than the other half once the fee is counted
+    await expectError(jointWithdraw(creator, contributed.sub(amount)), "ExceedsContribution");
+  });
+
+  it("Charges joint deposits and withdrawals at the member's negotiated rate", async () => {
+    const treasuryBefore = await balance(treasuryPDA);
+    const withdrawnBefore = (await memberEntry(partner)).withdrawn;
+    const amount = new anchor.BN(LAMPORTS_PER_SOL / 4);
+    await withFeeWaived(partner.publicKey, async () => {
+      const jointVault = jointVaultPDA(creator.publicKey);
+      const feeOverride = feeOverrideAccounts(partner.publicKey);
+      await jointDeposit(partner, LAMPORTS_PER_SOL / 2, jointVault, feeOverride);
+      await jointWithdraw(partner, amount, jointVault, feeOverride);
+    });
+
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+    assert.isTrue((await memberEntry(partner)).withdrawn.eq(withdrawnBefore.add(amount)));
+  });
+
+  it("Pays threshold withdrawals only once enough members approve", async () => {
+    const vaultId = new anchor.BN(2);
+    const jointVault = jointVaultPDA(creator.publicKey, vaultId);
+    await createJointVault(creator, [creator.publicKey, partner.publicKey], vaultId, {
+      threshold: { threshold: 2 },
+    });
+    await jointDeposit(creator, LAMPORTS_PER_SOL, jointVault);
+    await expectError(jointWithdraw(creator, new anchor.BN(1000), jointVault), "ApprovalRequired");
+
+    const destination = Keypair.generate().publicKey;
+    const proposal = pda(
+      Buffer.from("joint_withdrawal"),
+      jointVault.toBuffer(),
+      new anchor.BN(0).toArrayLike(Buffer, "le", 8)
+    );
+    await program.methods
+      .proposeJointWithdrawal(new anchor.BN(LAMPORTS_PER_SOL / 2), destination)
+      .accounts({
+        jointVault,
+        proposal,
+        member: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const executeWithdrawal = () =>
+      program.methods
+        .executeJointWithdrawal()
+        .accounts({
+          jointVault,
+          proposal,
+          funds: fundsPDA(jointVault),
+          treasuryConfig: treasuryConfigPDA,
+          statsShard: statsShardPDA(jointVault.toBuffer()[0] % STATS_SHARD_COUNT),
+          treasury: treasuryPDA,
+          destination,
+          member: partner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([partner])
+        .rpc();
+
+    await expectError(executeWithdrawal(), "ThresholdNotMet");
+    await program.methods
+      .approveJointWithdrawal()
+      .accounts({ jointVault, proposal, member: partner.publicKey })
+      .signers([partner])
+      .rpc();
+    await executeWithdrawal();
+
+    assert.equal(await balance(destination), LAMPORTS_PER_SOL / 2);
+    await expectError(executeWithdrawal(), "ProposalAlreadyExecuted");
+  });
+
+  it("Charges an executed threshold withdrawal at the executing member's rate", async () => {
+    const vaultId = new anchor.BN(3);
+    const jointVault = jointVaultPDA(creator.publicKey, vaultId);
+    await createJointVault(creator, [creator.publicKey, partner.publicKey], vaultId, {
+      threshold: { threshold: 2 },
+    });
+    await jointDeposit(creator, LAMPORTS_PER_SOL, jointVault);
+
+    const destination = Keypair.generate().publicKey;
+    const proposal = pda(
+      Buffer.from("joint_withdrawal"),
+      jointVault.toBuffer(),
+      new anchor.BN(0).toArrayLike(Buffer, "le", 8)
+    );
+    await program.methods
+      .proposeJointWithdrawal(new anchor.BN(LAMPORTS_PER_SOL / 2), destination)
+      .accounts({
+        jointVault,
+        proposal,
+        member: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+    await program.methods
+      .approveJointWithdrawal()
+      .accounts({ jointVault, proposal, member: partner.publicKey })
+      .signers([partner])
+      .rpc();
+
+    const treasuryBefore = await balance(treasuryPDA);
+    await withFeeWaived(partner.publicKey, () =>
+      program.methods
+        .executeJointWithdrawal()
+        .accounts({
+          jointVault,
+          proposal,
+          funds: fundsPDA(jointVault),
+          treasuryConfig: treasuryConfigPDA,
+          statsShard: statsShardPDA(jointVault.toBuffer()[0] % STATS_SHARD_COUNT),
+          treasury: treasuryPDA,
+          destination,
+          member: partner.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...feeOverrideAccounts(partner.publicKey),
+        })
+        .signers([partner])
+        .rpc()
+    );
+
+    assert.equal(await balance(treasuryPDA), treasuryBefore);
+    assert.equal(await balance(destination), LAMPORTS_PER_SOL / 2);
+  });
+});
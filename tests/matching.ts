@@ -0,0 +1,131 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  PLATFORM_FEE_BASIS_POINTS,
+  admin,
+  balance,
+  createUser,
+  expectError,
+  fundedKeypair,
+  pda,
+  program,
+  rolesPDA,
+  setupProtocol,
+  userAccounts,
+  vaultAccounts,
+} from "./fixture";
+
+describe("matching program", () => {
+  const PROGRAM_ID = new anchor.BN(1);
+  const MATCH_BASIS_POINTS = 5_000; // 50%
+
+  let sponsor: Keypair;
+  let user: Keypair;
+  let matchingProgram: PublicKey;
+  let matchingFunds: PublicKey;
+
+  const participantPDA = () =>
+    pda(Buffer.from("matching_participant"), matchingProgram.toBuffer(), user.publicKey.toBuffer());
+
+  const join = (approver: Keypair) =>
+    program.methods
+      .joinMatchingProgram()
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        matchingProgram,
+        sponsor: approver.publicKey,
+        matchingParticipant: participantPDA(),
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user, approver])
+      .rpc();
+
+  const matchedDeposit = (lamports: number) =>
+    program.methods
+      .deposit(new anchor.BN(lamports))
+      .accounts({
+        ...vaultAccounts(user.publicKey),
+        matchingProgram,
+        matchingFunds,
+        matchingParticipant: participantPDA(),
+      })
+      .signers([user])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    sponsor = await fundedKeypair();
+    user = await createUser();
+
+    matchingProgram = pda(
+      Buffer.from("matching_program"),
+      sponsor.publicKey.toBuffer(),
+      PROGRAM_ID.toArrayLike(Buffer, "le", 8)
+    );
+    matchingFunds = pda(Buffer.from("matching_funds"), matchingProgram.toBuffer());
+    await program.methods
+      .createMatchingProgram(
+        PROGRAM_ID,
+        MATCH_BASIS_POINTS,
+        new anchor.BN(LAMPORTS_PER_SOL),
+        new anchor.BN(3600)
+      )
+      .accounts({
+        matchingProgram,
+        funds: matchingFunds,
+        sponsor: sponsor.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([sponsor])
+      .rpc();
+    await program.methods
+      .fundMatchingProgram(new anchor.BN(2 * LAMPORTS_PER_SOL))
+      .accounts({
+        matchingProgram,
+        funds: matchingFunds,
+        sponsor: sponsor.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([sponsor])
+      .rpc();
+  });
+
+  it("Only enrolls users the sponsor approves", async () => {
+    await expectError(join(await fundedKeypair()), "Unauthorized");
+    await join(sponsor);
+    const participant = await program.account.matchingParticipant.fetch(participantPDA());
+    assert.isTrue(participant.user.equals(user.publicKey));
+  });
+
+  it("Pays the match into the vault alongside the deposit", async () => {
+    const vault = userAccounts(user.publicKey).vault;
+    const before = await balance(vault);
+
+    const amount = LAMPORTS_PER_SOL;
+    await matchedDeposit(amount);
+
+    const afterFee = amount - (amount * PLATFORM_FEE_BASIS_POINTS) / 10_000;
+    const matched = (afterFee * MATCH_BASIS_POINTS) / 10_000;
+    assert.equal((await balance(vault)) - before, afterFee + matched);
+  });
+
+  it("Counts the match against the vault balance cap", async () => {
+    const vault = userAccounts(user.publicKey).vault;
+    const amount = 0.2 * LAMPORTS_PER_SOL;
+    const afterFee = amount - (amount * PLATFORM_FEE_BASIS_POINTS) / 10_000;
+
+    // Room for the deposit itself, but not for the deposit plus its match
+    await program.methods
+      .setUserLimits(new anchor.BN((await balance(vault)) + afterFee), null)
+      .accounts({
+        roles: rolesPDA,
+        userConfig: userAccounts(user.publicKey).userConfig,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    await expectError(matchedDeposit(amount), "UserLimitExceeded");
+  });
+});
@@ -0,0 +1,68 @@
+import { Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { keccak_256 } from "@noble/hashes/sha3";
+import {
+  expectError,
+  fundedKeypair,
+  program,
+  setOnboardingGate,
+  setupProtocol,
+  treasuryConfigPDA,
+  userAccounts,
+} from "./fixture";
+
+// Mirrors verify_allowlist_proof in programs/auto-savings/src/lib.rs
+const hashPair = (a: Uint8Array, b: Uint8Array) =>
+  Buffer.compare(Buffer.from(a), Buffer.from(b)) <= 0
+    ? keccak_256(Buffer.concat([a, b]))
+    : keccak_256(Buffer.concat([b, a]));
+
+describe("onboarding gate", () => {
+  const verifier = Keypair.generate();
+
+  const fundedUser = () => fundedKeypair(LAMPORTS_PER_SOL);
+
+  const initializeUser = (user: Keypair, proof: number[][], coSigner?: Keypair) =>
+    program.methods
+      .initializeUser(proof)
+      .accounts({
+        treasuryConfig: treasuryConfigPDA,
+        userConfig: userAccounts(user.publicKey).userConfig,
+        vault: userAccounts(user.publicKey).vault,
+        verifier: coSigner ? coSigner.publicKey : null,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(coSigner ? [user, coSigner] : [user])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+  });
+
+  after(async () => {
+    await setOnboardingGate(null, null);
+  });
+
+  it("Requires the verifier co-signature while gated by verifier", async () => {
+    await setOnboardingGate(verifier.publicKey, null);
+
+    const rejected = await fundedUser();
+    await expectError(initializeUser(rejected, []), "NotAllowlisted");
+
+    const admitted = await fundedUser();
+    await initializeUser(admitted, [], verifier);
+  });
+
+  it("Admits users with a valid allowlist proof", async () => {
+    const allowed = await fundedUser();
+    const other = await fundedUser();
+    const leaves = [allowed, other].map((kp) => keccak_256(kp.publicKey.toBuffer()));
+    const root = hashPair(leaves[0], leaves[1]);
+    await setOnboardingGate(null, Array.from(root));
+
+    await initializeUser(allowed, [Array.from(leaves[1])]);
+
+    const stranger = await fundedUser();
+    await expectError(initializeUser(stranger, [Array.from(leaves[0])]), "NotAllowlisted");
+  });
+});
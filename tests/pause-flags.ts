@@ -0,0 +1,69 @@
+import { LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  MAX_WITHDRAWAL_PAUSE_SECONDS,
+  PAUSE_DEPOSITS,
+  PAUSE_WITHDRAWALS,
+  deposit,
+  expectError,
+  program,
+  setPauseMask,
+  setupProtocol,
+  treasuryConfigPDA,
+  waitForClusterTime,
+  withdraw,
+} from "./fixture";
+
+describe("pause flags", () => {
+  before(async () => {
+    await setupProtocol();
+    await deposit(LAMPORTS_PER_SOL);
+  });
+
+  after(async () => {
+    await setPauseMask(0);
+  });
+
+  it("Blocks deposits but not withdrawals when only deposits are paused", async () => {
+    await setPauseMask(PAUSE_DEPOSITS);
+
+    await expectError(deposit(0.1 * LAMPORTS_PER_SOL), "ProtocolPaused");
+    await withdraw(0.01 * LAMPORTS_PER_SOL);
+    await setPauseMask(0);
+  });
+
+  it("Rejects withdrawal pauses longer than the maximum duration", async () => {
+    await expectError(
+      setPauseMask(PAUSE_WITHDRAWALS, MAX_WITHDRAWAL_PAUSE_SECONDS + 1),
+      "WithdrawalPauseTooLong"
+    );
+    await expectError(setPauseMask(PAUSE_WITHDRAWALS, 0), "WithdrawalPauseTooLong");
+  });
+
+  it("Auto-unpauses withdrawals once the pause window lapses", async () => {
+    await setPauseMask(PAUSE_WITHDRAWALS, 2);
+    await expectError(withdraw(0.01 * LAMPORTS_PER_SOL), "ProtocolPaused");
+
+    const config = await program.account.treasuryConfig.fetch(treasuryConfigPDA);
+    await waitForClusterTime(config.withdrawalsPausedUntil.toNumber());
+
+    // Flag is still set on the account, but the window has expired
+    assert.equal(config.pauseMask & PAUSE_WITHDRAWALS, PAUSE_WITHDRAWALS);
+    await withdraw(0.01 * LAMPORTS_PER_SOL);
+  });
+
+  it("Clears a lapsed withdrawal bit on the next mask update", async () => {
+    // Echo the stale mask back while pausing deposits; no new withdrawal window is armed
+    await setPauseMask(PAUSE_WITHDRAWALS | PAUSE_DEPOSITS, 0);
+
+    const config = await program.account.treasuryConfig.fetch(treasuryConfigPDA);
+    assert.equal(config.pauseMask, PAUSE_DEPOSITS);
+    await withdraw(0.01 * LAMPORTS_PER_SOL);
+
+    await setPauseMask(0);
+  });
+
+  it("Refuses to immediately re-pause withdrawals after a window lapses", async () => {
+    await expectError(setPauseMask(PAUSE_WITHDRAWALS, 60), "WithdrawalPauseCooldown");
+  });
+});
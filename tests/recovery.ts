@@ -0,0 +1,186 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createMint } from "@solana/spl-token";
+import { assert } from "chai";
+import {
+  admin,
+  createUser,
+  deposit,
+  expectError,
+  fundedKeypair,
+  pda,
+  program,
+  provider,
+  setupProtocol,
+  userAccounts,
+} from "./fixture";
+
+// The one-day recovery delay can't be skipped on a local validator, so these cover
+// everything up to execution plus the gates on the post-recovery hand-over
+describe("recovery", () => {
+  const RECOVERY_DELAY_SECONDS = 24 * 60 * 60;
+
+  let owner: Keypair;
+  let newOwner: Keypair;
+  let guardians: Keypair[];
+  let mint: PublicKey;
+
+  const recoveryPDA = (user: PublicKey) => pda(Buffer.from("recovery"), user.toBuffer());
+  const tokenVaultPDA = (user: PublicKey) =>
+    pda(Buffer.from("token_vault"), user.toBuffer(), mint.toBuffer());
+
+  const setGuardians = (keys: PublicKey[], threshold: number, delaySeconds: number) =>
+    program.methods
+      .setRecoveryGuardians(keys, threshold, new anchor.BN(delaySeconds))
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        recoveryConfig: recoveryPDA(owner.publicKey),
+        user: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+  const guardianAction = (
+    method: "initiateRecovery" | "approveRecovery",
+    guardian: Keypair,
+    proposedOwner = newOwner.publicKey
+  ) =>
+    program.methods[method](proposedOwner)
+      .accounts({ recoveryConfig: recoveryPDA(owner.publicKey), guardian: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+
+  const executeRecovery = () => {
+    const oldAccounts = userAccounts(owner.publicKey);
+    const newAccounts = userAccounts(newOwner.publicKey);
+    return program.methods
+      .executeRecovery()
+      .accounts({
+        recoveryConfig: recoveryPDA(owner.publicKey),
+        oldOwner: owner.publicKey,
+        oldUserConfig: oldAccounts.userConfig,
+        oldVault: oldAccounts.vault,
+        newUserConfig: newAccounts.userConfig,
+        newVault: newAccounts.vault,
+        oldStatsShard: oldAccounts.statsShard,
+        newStatsShard: newAccounts.statsShard,
+        newOwner: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+  };
+
+  before(async () => {
+    await setupProtocol();
+    owner = await createUser();
+    newOwner = await fundedKeypair();
+    guardians = [await fundedKeypair(), await fundedKeypair()];
+    await deposit(LAMPORTS_PER_SOL, owner);
+
+    mint = await createMint(provider.connection, admin.payer, admin.publicKey, null, 6);
+    await program.methods
+      .initializeTokenVault()
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        mint,
+        tokenVault: tokenVaultPDA(owner.publicKey),
+        user: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  });
+
+  it("Rejects guardian sets that would weaken the recovery", async () => {
+    const keys = guardians.map((guardian) => guardian.publicKey);
+    await expectError(setGuardians(keys, 3, RECOVERY_DELAY_SECONDS), "InvalidGuardians");
+    await expectError(setGuardians(keys, 2, RECOVERY_DELAY_SECONDS - 1), "InvalidGuardians");
+    await expectError(
+      setGuardians([owner.publicKey, keys[0]], 1, RECOVERY_DELAY_SECONDS),
+      "InvalidGuardians"
+    );
+  });
+
+  it("Holds an approved recovery for the delay", async () => {
+    await setGuardians(
+      guardians.map((guardian) => guardian.publicKey),
+      2,
+      RECOVERY_DELAY_SECONDS
+    );
+
+    await expectError(guardianAction("initiateRecovery", newOwner), "Unauthorized");
+    await guardianAction("initiateRecovery", guardians[0]);
+    await expectError(
+      guardianAction("approveRecovery", guardians[1], Keypair.generate().publicKey),
+      "InvalidRecoveryOwner"
+    );
+    await guardianAction("approveRecovery", guardians[1]);
+    await expectError(executeRecovery(), "TimelockNotElapsed");
+  });
+
+  it("Keeps the old owner's token vault and allowances out of reach until recovered", async () => {
+    await expectError(
+      program.methods
+        .recoverTokenVault()
+        .accounts({
+          oldOwner: owner.publicKey,
+          oldUserConfig: userAccounts(owner.publicKey).userConfig,
+          mint,
+          oldTokenVault: tokenVaultPDA(owner.publicKey),
+          newTokenVault: tokenVaultPDA(newOwner.publicKey),
+          newOwner: newOwner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newOwner])
+        .rpc(),
+      "InvalidRecoveryOwner"
+    );
+
+    const spender = Keypair.generate().publicKey;
+    const allowance = pda(Buffer.from("allowance"), owner.publicKey.toBuffer(), spender.toBuffer());
+    await program.methods
+      .grantAllowance(owner.publicKey, new anchor.BN(1000), new anchor.BN(60))
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        allowance,
+        spender,
+        user: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+    await expectError(
+      program.methods
+        .revokeRecoveredAllowance()
+        .accounts({
+          oldOwner: owner.publicKey,
+          oldUserConfig: userAccounts(owner.publicKey).userConfig,
+          allowance,
+          newOwner: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc(),
+      "InvalidRecoveryOwner"
+    );
+  });
+
+  it("Lets the owner cancel during the delay", async () => {
+    await program.methods
+      .cancelRecovery()
+      .accounts({
+        userConfig: userAccounts(owner.publicKey).userConfig,
+        recoveryConfig: recoveryPDA(owner.publicKey),
+        user: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    const recoveryConfig = await program.account.recoveryConfig.fetch(recoveryPDA(owner.publicKey));
+    assert.isTrue(recoveryConfig.pendingOwner.equals(PublicKey.default));
+    await expectError(executeRecovery(), "InvalidRecoveryOwner");
+  });
+});
@@ -0,0 +1,163 @@
+import * as anchor from "@coral-xyz/anchor";
+import {
+  Keypair,
+  SystemProgram,
+  Transaction,
+  LAMPORTS_PER_SOL,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  admin,
+  aggregateStats,
+  balance,
+  createUser,
+  deposit,
+  program,
+  provider,
+  rewardFundsPDA,
+  rewardPoolPDA,
+  rolesPDA,
+  setupProtocol,
+  setupSharePool,
+  shareMintPDA,
+  sharePoolPDA,
+  shareVaultPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  waitForClusterTime,
+} from "./fixture";
+
+describe("rewards", () => {
+  let user: Keypair;
+
+  const pendingRewards = async () =>
+    (await program.account.userConfig.fetch(userAccounts(user.publicKey).userConfig))
+      .pendingRewards;
+
+  const syncRewards = () => {
+    const { userConfig, vault, statsShard } = userAccounts(user.publicKey);
+    return program.methods
+      .syncRewards()
+      .accounts({ userConfig, vault, statsShard, rewardPool: rewardPoolPDA })
+      .rpc();
+  };
+
+  const rewardStake = async () => {
+    const { rewardBalance, netDeposited } = await program.account.userConfig.fetch(
+      userAccounts(user.publicKey).userConfig
+    );
+    return { rewardBalance, netDeposited };
+  };
+
+  const claimRewards = () =>
+    program.methods
+      .claimRewards()
+      .accounts({
+        ...userAccounts(user.publicKey),
+        rewardPool: rewardPoolPDA,
+        rewardFunds: rewardFundsPDA,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser(20 * LAMPORTS_PER_SOL);
+    await deposit(5 * LAMPORTS_PER_SOL, user);
+  });
+
+  it("Stakes each shard in the pool when the crank settles it", async () => {
+    const { statsShard } = userAccounts(user.publicKey);
+    const { rewardBalance } = await program.account.userConfig.fetch(
+      userAccounts(user.publicKey).userConfig
+    );
+    assert.isTrue(rewardBalance.gte(new anchor.BN(5 * LAMPORTS_PER_SOL * 0.99)));
+
+    await aggregateStats();
+    const shard = await program.account.statsShard.fetch(statsShard);
+    assert.isTrue(shard.poolStaked.eq(shard.rewardStaked));
+    assert.isTrue(shard.rewardStaked.gte(rewardBalance));
+  });
+
+  it("Accrues rewards through the shard and pays them out on claim", async () => {
+    await program.methods
+      .distributeRewards(new anchor.BN(0.01 * LAMPORTS_PER_SOL), new anchor.BN(2))
+      .accounts({
+        roles: rolesPDA,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        rewardPool: rewardPoolPDA,
+        rewardFunds: rewardFundsPDA,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    const { epochEndTs } = await program.account.rewardPool.fetch(rewardPoolPDA);
+    await waitForClusterTime(epochEndTs.toNumber());
+
+    // Anyone can bring the user's rewards up to date
+    await syncRewards();
+    const pending = await pendingRewards();
+    assert.isTrue(pending.gtn(0));
+
+    const fundsBefore = await balance(rewardFundsPDA);
+    await claimRewards();
+    assert.equal(fundsBefore - (await balance(rewardFundsPDA)), pending.toNumber());
+    assert.isTrue((await pendingRewards()).eqn(0));
+  });
+
+  it("Treats a claim with nothing accrued as a no-op", async () => {
+    const fundsBefore = await balance(rewardFundsPDA);
+    await claimRewards();
+    assert.equal(await balance(rewardFundsPDA), fundsBefore);
+  });
+
+  it("Stakes tracked principal, not lamports sent straight to the vault", async () => {
+    const { vault } = userAccounts(user.publicKey);
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: user.publicKey,
+          toPubkey: vault,
+          lamports: 5 * LAMPORTS_PER_SOL,
+        })
+      ),
+      [user]
+    );
+    await syncRewards();
+
+    const { rewardBalance, netDeposited } = await rewardStake();
+    assert.isTrue(rewardBalance.eq(netDeposited));
+    assert.isBelow(rewardBalance.toNumber(), 5 * LAMPORTS_PER_SOL);
+  });
+
+  it("Stakes the pooled position alongside the vault", async () => {
+    await setupSharePool();
+    const before = (await rewardStake()).rewardBalance;
+    const { userConfig, statsShard } = userAccounts(user.publicKey);
+    await program.methods
+      .depositPooled(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({
+        userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        shareVault: shareVaultPDA,
+        statsShard,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const { rewardBalance, netDeposited } = await rewardStake();
+    assert.isTrue(rewardBalance.eq(netDeposited));
+    assert.isTrue(rewardBalance.gt(before));
+  });
+});
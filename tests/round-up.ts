@@ -0,0 +1,198 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  createMint,
+  getAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import {
+  PLATFORM_FEE_BASIS_POINTS,
+  admin,
+  createUser,
+  expectError,
+  feeOverrideAccounts,
+  fundedKeypair,
+  pda,
+  program,
+  provider,
+  rolesPDA,
+  setupProtocol,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  withFeeWaived,
+} from "./fixture";
+
+describe("round-up", () => {
+  let user: Keypair;
+  const recipient = Keypair.generate().publicKey;
+
+  const tokenVaultConfigPDA = (mint: PublicKey) =>
+    pda(Buffer.from("token_vault_config"), user.publicKey.toBuffer(), mint.toBuffer());
+  const tokenVaultPDA = (mint: PublicKey) =>
+    pda(Buffer.from("token_vault"), user.publicKey.toBuffer(), mint.toBuffer());
+
+  // A mint with `decimals`, the user holding 100 whole tokens, and the user's round-up
+  // unit for it set to one whole token
+  const setUpMint = async (decimals: number) => {
+    const wholeToken = 10 ** decimals;
+    const mint = await createMint(provider.connection, admin.payer, admin.publicKey, null, decimals);
+    const userTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      admin.payer,
+      mint,
+      user.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      admin.payer,
+      mint,
+      userTokenAccount,
+      admin.payer,
+      100 * wholeToken
+    );
+
+    await program.methods
+      .setRoundUpUnit(new anchor.BN(wholeToken))
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        mint,
+        tokenVaultConfig: tokenVaultConfigPDA(mint),
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    return { mint, userTokenAccount, wholeToken };
+  };
+
+  const processTokenTransfer = async (
+    mint: PublicKey,
+    userTokenAccount: PublicKey,
+    amount: anchor.BN,
+    feeOverride: object = {}
+  ) =>
+    program.methods
+      .processTokenTransfer(amount)
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        mint,
+        userTokenAccount,
+        destinationTokenAccount: await createAssociatedTokenAccount(
+          provider.connection,
+          admin.payer,
+          mint,
+          recipient
+        ),
+        tokenVaultConfig: tokenVaultConfigPDA(mint),
+        tokenVault: tokenVaultPDA(mint),
+        treasury: treasuryPDA,
+        treasuryTokenAccount: pda(Buffer.from("treasury_token"), mint.toBuffer()),
+        user: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ...feeOverride,
+      })
+      .signers([user])
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser();
+  });
+
+  it("Rounds each mint up to its own unit", async () => {
+    for (const decimals of [6, 9]) {
+      const { mint, userTokenAccount, wholeToken } = await setUpMint(decimals);
+
+      // 4.3 tokens rounds up to 5, saving 0.7 tokens less the fee
+      await processTokenTransfer(mint, userTokenAccount, new anchor.BN((43 * wholeToken) / 10));
+
+      const roundUp = new anchor.BN((7 * wholeToken) / 10);
+      const saved = roundUp.sub(roundUp.muln(PLATFORM_FEE_BASIS_POINTS).divn(10_000));
+      const vault = await getAccount(provider.connection, tokenVaultPDA(mint));
+      assert.equal(vault.amount.toString(), saved.toString());
+    }
+  });
+
+  it("Lets the treasurer withdraw token fees", async () => {
+    const { mint, userTokenAccount } = await setUpMint(6);
+    await processTokenTransfer(mint, userTokenAccount, new anchor.BN(4_300_000));
+
+    const treasuryTokenAccount = pda(Buffer.from("treasury_token"), mint.toBuffer());
+    const fees = (await getAccount(provider.connection, treasuryTokenAccount)).amount;
+    assert.isTrue(fees > BigInt(0));
+
+    const feeCollector = await fundedKeypair();
+    const destinationTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      admin.payer,
+      mint,
+      feeCollector.publicKey
+    );
+    const withdrawTreasuryTokens = (signer: Keypair) =>
+      program.methods
+        .withdrawTreasuryTokens(new anchor.BN(fees.toString()))
+        .accounts({
+          roles: rolesPDA,
+          treasuryConfig: treasuryConfigPDA,
+          mint,
+          treasury: treasuryPDA,
+          treasuryTokenAccount,
+          destinationTokenAccount,
+          admin: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    await expectError(withdrawTreasuryTokens(feeCollector), "MissingRole");
+    await withdrawTreasuryTokens(admin.payer);
+
+    const collected = await getAccount(provider.connection, destinationTokenAccount);
+    assert.equal(collected.amount, fees);
+    assert.equal((await getAccount(provider.connection, treasuryTokenAccount)).amount, BigInt(0));
+  });
+
+  it("Charges round-ups and token withdrawals at the user's negotiated rate", async () => {
+    const { mint, userTokenAccount, wholeToken } = await setUpMint(6);
+    const treasuryTokenAccount = pda(Buffer.from("treasury_token"), mint.toBuffer());
+    const feeOverride = feeOverrideAccounts(user.publicKey);
+
+    await withFeeWaived(user.publicKey, async () => {
+      await processTokenTransfer(
+        mint,
+        userTokenAccount,
+        new anchor.BN((43 * wholeToken) / 10),
+        feeOverride
+      );
+      // The whole round-up is saved
+      const saved = (7 * wholeToken) / 10;
+      const vault = await getAccount(provider.connection, tokenVaultPDA(mint));
+      assert.equal(vault.amount.toString(), saved.toString());
+
+      await program.methods
+        .withdrawTokenSavings(new anchor.BN(saved))
+        .accounts({
+          userConfig: userAccounts(user.publicKey).userConfig,
+          treasuryConfig: treasuryConfigPDA,
+          mint,
+          userTokenAccount,
+          tokenVault: tokenVaultPDA(mint),
+          treasuryTokenAccount,
+          user: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          ...feeOverride,
+        })
+        .signers([user])
+        .rpc();
+    });
+
+    assert.equal((await getAccount(provider.connection, tokenVaultPDA(mint))).amount, BigInt(0));
+    assert.equal((await getAccount(provider.connection, treasuryTokenAccount)).amount, BigInt(0));
+  });
+});
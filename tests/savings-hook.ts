@@ -0,0 +1,296 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  AccountMeta,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createApproveCheckedInstruction,
+  createAssociatedTokenAccount,
+  createInitializeMintInstruction,
+  createInitializeTransferHookInstruction,
+  createTransferCheckedInstruction,
+  getAccount,
+  getMintLen,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { SavingsHook } from "../target/types/savings_hook";
+import {
+  admin,
+  createUser,
+  exists,
+  expectError,
+  pda,
+  program,
+  provider,
+  setupProtocol,
+  treasuryConfigPDA,
+  userAccounts,
+} from "./fixture";
+
+describe("savings hook", () => {
+  const hook = anchor.workspace.SavingsHook as Program<SavingsHook>;
+  const DECIMALS = 6;
+  const SAVINGS_RATE = 10;
+
+  let user: Keypair;
+  let mint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let recipientTokenAccount: PublicKey;
+
+  const hookPDA = (...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, hook.programId)[0];
+  const pendingSavingsPDA = (owner: PublicKey) =>
+    hookPDA(Buffer.from("pending_savings"), owner.toBuffer(), mint.toBuffer());
+  const tokenVaultPDA = () =>
+    pda(Buffer.from("token_vault"), user.publicKey.toBuffer(), mint.toBuffer());
+
+  // Extra accounts Token-2022 resolves for a transfer out of `owner`'s token account,
+  // whoever signs for it
+  const hookAccounts = (owner: PublicKey): AccountMeta[] => [
+    { pubkey: hook.programId, isSigner: false, isWritable: false },
+    {
+      pubkey: hookPDA(Buffer.from("extra-account-metas"), mint.toBuffer()),
+      isSigner: false,
+      isWritable: false,
+    },
+    { pubkey: program.programId, isSigner: false, isWritable: false },
+    { pubkey: userAccounts(owner).userConfig, isSigner: false, isWritable: false },
+    { pubkey: pendingSavingsPDA(owner), isSigner: false, isWritable: true },
+  ];
+
+  const setSavingsRate = (rate: number) =>
+    program.methods
+      .setSavingsRate(rate)
+      .accounts({ userConfig: userAccounts(user.publicKey).userConfig, user: user.publicKey })
+      .signers([user])
+      .rpc();
+
+  const pending = async () =>
+    (await hook.account.pendingSavings.fetch(pendingSavingsPDA(user.publicKey))).pending;
+
+  const settleHookSavings = (amount: anchor.BN) =>
+    program.methods
+      .settleHookSavings(amount)
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        mint,
+        ownerTokenAccount: userTokenAccount,
+        tokenVault: tokenVaultPDA(),
+        pendingSavings: pendingSavingsPDA(user.publicKey),
+        savingsHookProgram: hook.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts(hookAccounts(user.publicKey))
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser();
+
+    // Token-2022 mint whose transfers run through the savings hook
+    const mintKeypair = Keypair.generate();
+    mint = mintKeypair.publicKey;
+    const mintLen = getMintLen([ExtensionType.TransferHook]);
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          newAccountPubkey: mint,
+          space: mintLen,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(mintLen),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferHookInstruction(
+          mint,
+          admin.publicKey,
+          hook.programId,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(mint, DECIMALS, admin.publicKey, null, TOKEN_2022_PROGRAM_ID)
+      ),
+      [admin.payer, mintKeypair]
+    );
+    await hook.methods
+      .initializeExtraAccountMetaList()
+      .accounts({
+        extraAccountMetaList: hookPDA(Buffer.from("extra-account-metas"), mint.toBuffer()),
+        mint,
+        authority: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    userTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      admin.payer,
+      mint,
+      user.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    recipientTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      admin.payer,
+      mint,
+      Keypair.generate().publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      provider.connection,
+      admin.payer,
+      mint,
+      userTokenAccount,
+      admin.payer,
+      1_000_000_000,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await program.methods
+      .initializeTokenVault()
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        mint,
+        tokenVault: tokenVaultPDA(),
+        user: user.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    await hook.methods
+      .enableHookSavings()
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        mint,
+        pendingSavings: pendingSavingsPDA(user.publicKey),
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    await setSavingsRate(SAVINGS_RATE);
+  });
+
+  it("Accrues the savings rate of each transfer and settles it into the token vault", async () => {
+    const amount = 100_000_000;
+    const transfer = createTransferCheckedInstruction(
+      userTokenAccount,
+      mint,
+      recipientTokenAccount,
+      user.publicKey,
+      amount,
+      DECIMALS,
+      [],
+      TOKEN_2022_PROGRAM_ID
+    );
+    transfer.keys.push(...hookAccounts(user.publicKey));
+    await sendAndConfirmTransaction(provider.connection, new Transaction().add(transfer), [
+      admin.payer,
+      user,
+    ]);
+    const owed = (amount * SAVINGS_RATE) / 100;
+    assert.isTrue((await pending()).eqn(owed));
+
+    // The owner lets their vault pull what is owed; anyone can then crank it in
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        createApproveCheckedInstruction(
+          userTokenAccount,
+          mint,
+          tokenVaultPDA(),
+          user.publicKey,
+          owed * 2,
+          DECIMALS,
+          [],
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [admin.payer, user]
+    );
+    await expectError(settleHookSavings(new anchor.BN(owed + 1)), "ExceedsPending");
+    await settleHookSavings(new anchor.BN(owed));
+
+    const vault = await getAccount(
+      provider.connection,
+      tokenVaultPDA(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(vault.amount.toString(), owed.toString());
+    assert.isTrue((await pending()).eqn(0));
+    const pendingSavings = await hook.account.pendingSavings.fetch(
+      pendingSavingsPDA(user.publicKey)
+    );
+    assert.isTrue(pendingSavings.totalSettled.eqn(owed));
+  });
+
+  it("Accrues on the owner's savings when a delegate makes the transfer", async () => {
+    const delegate = Keypair.generate();
+    const amount = 50_000_000;
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        createApproveCheckedInstruction(
+          userTokenAccount,
+          mint,
+          delegate.publicKey,
+          user.publicKey,
+          amount,
+          DECIMALS,
+          [],
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [admin.payer, user]
+    );
+
+    const transfer = createTransferCheckedInstruction(
+      userTokenAccount,
+      mint,
+      recipientTokenAccount,
+      delegate.publicKey,
+      amount,
+      DECIMALS,
+      [],
+      TOKEN_2022_PROGRAM_ID
+    );
+    transfer.keys.push(...hookAccounts(user.publicKey));
+    await sendAndConfirmTransaction(provider.connection, new Transaction().add(transfer), [
+      admin.payer,
+      delegate,
+    ]);
+
+    assert.isTrue((await pending()).eqn((amount * SAVINGS_RATE) / 100));
+  });
+
+  it("Turns hook savings off with a zero rate and closes the pending account", async () => {
+    await setSavingsRate(0);
+    const config = await program.account.userConfig.fetch(userAccounts(user.publicKey).userConfig);
+    assert.equal(config.savingsRate, 0);
+
+    await hook.methods
+      .closePendingSavings()
+      .accounts({
+        userConfig: userAccounts(user.publicKey).userConfig,
+        pendingSavings: pendingSavingsPDA(user.publicKey),
+        authority: user.publicKey,
+      })
+      .signers([user])
+      .rpc();
+    assert.isFalse(await exists(pendingSavingsPDA(user.publicKey)));
+  });
+});
@@ -0,0 +1,463 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMint,
+  transfer,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import {
+  PAUSE_DEPOSITS,
+  admin,
+  createUser,
+  exists,
+  expectError,
+  pda,
+  program,
+  provider,
+  rolesPDA,
+  setPauseMask,
+  setupProtocol,
+  setupSharePool,
+  shareMintPDA,
+  sharePoolPDA,
+  shareVaultPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  waitForClusterTime,
+} from "./fixture";
+
+describe("share pool", () => {
+  const { userConfig: userConfigPDA, vault: vaultPDA, statsShard: statsShardPDA } =
+    userAccounts(admin.publicKey);
+  const userShares = getAssociatedTokenAddressSync(shareMintPDA, admin.publicKey);
+  const withdrawalQueuePDA = pda(Buffer.from("withdrawal_queue"));
+
+  const poolAccounts = {
+    treasuryConfig: treasuryConfigPDA,
+    treasury: treasuryPDA,
+    sharePool: sharePoolPDA,
+    shareMint: shareMintPDA,
+    shareVault: shareVaultPDA,
+    userShares,
+    user: admin.publicKey,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+  };
+
+  const pooledAccounts = {
+    userConfig: userConfigPDA,
+    treasuryConfig: treasuryConfigPDA,
+    treasury: treasuryPDA,
+    sharePool: sharePoolPDA,
+    shareMint: shareMintPDA,
+    shareVault: shareVaultPDA,
+    statsShard: statsShardPDA,
+    user: admin.publicKey,
+    systemProgram: SystemProgram.programId,
+  };
+
+  const migrateAccounts = {
+    userConfig: userConfigPDA,
+    vault: vaultPDA,
+    treasuryConfig: treasuryConfigPDA,
+    statsShard: statsShardPDA,
+    sharePool: sharePoolPDA,
+    shareMint: shareMintPDA,
+    shareVault: shareVaultPDA,
+    user: admin.publicKey,
+    systemProgram: SystemProgram.programId,
+  };
+
+  // freeze_user/unfreeze_user accounts that also freeze or thaw the holder's shares
+  const manageAccountsFor = (holder: PublicKey) => ({
+    roles: rolesPDA,
+    userConfig: userAccounts(holder).userConfig,
+    sharePool: sharePoolPDA,
+    shareMint: shareMintPDA,
+    ownerShares: getAssociatedTokenAddressSync(shareMintPDA, holder),
+    admin: admin.publicKey,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  const pooledShares = async () =>
+    (await program.account.userConfig.fetch(userConfigPDA)).pooledShares;
+
+  // Lamports the admin's shares currently redeem for, before fees
+  const positionValue = async () => {
+    const pool = await program.account.sharePool.fetch(sharePoolPDA);
+    const supply = (await getMint(provider.connection, shareMintPDA)).supply;
+    const shares = (await getAccount(provider.connection, userShares)).amount;
+    return new anchor.BN(shares.toString())
+      .mul(pool.totalAssets)
+      .div(new anchor.BN(supply.toString()));
+  };
+
+  const ticketAt = (id: anchor.BN) =>
+    pda(Buffer.from("withdrawal_ticket"), id.toArrayLike(Buffer, "le", 8));
+
+  const claimTicket = (id: anchor.BN, owner: PublicKey) =>
+    program.methods
+      .claimWithdrawal()
+      .accounts({
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareVault: shareVaultPDA,
+        withdrawalQueue: withdrawalQueuePDA,
+        ticket: ticketAt(id),
+        owner,
+        ownerConfig: userAccounts(owner).userConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+  const setWithdrawalNotice = (noticeSeconds: number) =>
+    program.methods
+      .setWithdrawalNotice(new anchor.BN(noticeSeconds))
+      .accounts({ roles: rolesPDA, withdrawalQueue: withdrawalQueuePDA, admin: admin.publicKey })
+      .rpc();
+
+  before(async () => {
+    await setupProtocol();
+    await setupSharePool();
+
+    if (!(await exists(withdrawalQueuePDA))) {
+      await program.methods
+        .initializeWithdrawalQueue(new anchor.BN(0))
+        .accounts({
+          roles: rolesPDA,
+          withdrawalQueue: withdrawalQueuePDA,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  it("Mints shares on deposit and raises their value as yield accrues", async () => {
+    await program.methods
+      .depositShares(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({
+        ...poolAccounts,
+        userConfig: userConfigPDA,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const before = await positionValue();
+    assert.isTrue(before.gtn(0));
+
+    await program.methods
+      .accrueShareYield(new anchor.BN(1_000_000))
+      .accounts({
+        roles: rolesPDA,
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        shareVault: shareVaultPDA,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    assert.isTrue((await positionValue()).gt(before));
+  });
+
+  it("Burns shares for SOL on redeem", async () => {
+    const shares = (await getAccount(provider.connection, userShares)).amount;
+    const balanceBefore = await provider.connection.getBalance(admin.publicKey);
+
+    await program.methods
+      .redeemShares(new anchor.BN(shares.toString()))
+      .accounts({ ...poolAccounts, userConfig: userConfigPDA })
+      .rpc();
+
+    assert.equal((await getAccount(provider.connection, userShares)).amount.toString(), "0");
+    assert.isAbove(await provider.connection.getBalance(admin.publicKey), balanceBefore);
+  });
+
+  it("Refuses to redeem shares held by a frozen account, or moved out of it", async () => {
+    const holder = await createUser();
+    const { userConfig } = userAccounts(holder.publicKey);
+    const holderAccounts = {
+      ...poolAccounts,
+      userConfig,
+      userShares: getAssociatedTokenAddressSync(shareMintPDA, holder.publicKey),
+      user: holder.publicKey,
+    };
+    await program.methods
+      .depositShares(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({ ...holderAccounts, associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID })
+      .signers([holder])
+      .rpc();
+    const shares = (await getAccount(provider.connection, holderAccounts.userShares)).amount;
+
+    const manageAccounts = manageAccountsFor(holder.publicKey);
+    await program.methods.freezeUser(admin.publicKey).accounts(manageAccounts).rpc();
+    assert.isTrue((await getAccount(provider.connection, holderAccounts.userShares)).isFrozen);
+    await expectError(
+      program.methods
+        .redeemShares(new anchor.BN(shares.toString()))
+        .accounts(holderAccounts)
+        .signers([holder])
+        .rpc(),
+      "AccountFrozen"
+    );
+
+    // Moving the shares to another wallet to redeem them there fails at the transfer
+    const accomplice = Keypair.generate();
+    const accompliceShares = await createAssociatedTokenAccount(
+      provider.connection,
+      admin.payer,
+      shareMintPDA,
+      accomplice.publicKey
+    );
+    await expectError(
+      transfer(
+        provider.connection,
+        admin.payer,
+        holderAccounts.userShares,
+        accompliceShares,
+        holder,
+        shares
+      ),
+      "0x11"
+    );
+    await expectError(
+      program.methods
+        .redeemShares(new anchor.BN(shares.toString()))
+        .accounts({
+          ...poolAccounts,
+          userConfig: userAccounts(accomplice.publicKey).userConfig,
+          userShares: accompliceShares,
+          user: accomplice.publicKey,
+        })
+        .signers([accomplice])
+        .rpc(),
+      "0x1"
+    );
+
+    await program.methods.unfreezeUser().accounts(manageAccounts).rpc();
+    const before = (await program.account.userConfig.fetch(userConfig)).lastActivityTs.toNumber();
+    await waitForClusterTime(before + 2);
+    await program.methods
+      .redeemShares(new anchor.BN(shares.toString()))
+      .accounts(holderAccounts)
+      .signers([holder])
+      .rpc();
+
+    // Redeeming counts as activity for the holder's inheritance timer
+    const after = (await program.account.userConfig.fetch(userConfig)).lastActivityTs.toNumber();
+    assert.isAbove(after, before);
+  });
+
+  it("Moves a pooled position into the personal vault and back", async () => {
+    const startingShares = await pooledShares();
+    await program.methods
+      .depositPooled(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts(pooledAccounts)
+      .rpc();
+
+    const deposited = (await pooledShares()).sub(startingShares);
+    assert.isTrue(deposited.gtn(0));
+
+    const vaultBefore = await provider.connection.getBalance(vaultPDA);
+    await program.methods.migrateToPersonal(deposited).accounts(migrateAccounts).rpc();
+    assert.isTrue((await pooledShares()).eq(startingShares));
+    const migrated = (await provider.connection.getBalance(vaultPDA)) - vaultBefore;
+    assert.isAbove(migrated, 0);
+
+    await program.methods
+      .migrateToPool(new anchor.BN(migrated))
+      .accounts(migrateAccounts)
+      .rpc();
+    assert.isTrue((await pooledShares()).gt(startingShares));
+
+    await program.methods
+      .withdrawPooled((await pooledShares()).sub(startingShares))
+      .accounts(pooledAccounts)
+      .rpc();
+    assert.isTrue((await pooledShares()).eq(startingShares));
+  });
+
+  it("Stops migrating into the pool while deposits are paused", async () => {
+    await setPauseMask(PAUSE_DEPOSITS);
+    await expectError(
+      program.methods.migrateToPool(new anchor.BN(1000)).accounts(migrateAccounts).rpc(),
+      "ProtocolPaused"
+    );
+    await setPauseMask(0);
+  });
+
+  it("Pays queued withdrawals in ticket order", async () => {
+    const startingShares = await pooledShares();
+    await program.methods
+      .depositPooled(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts(pooledAccounts)
+      .rpc();
+
+    const queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePDA);
+    const ticketPDA = pda(Buffer.from("withdrawal_ticket"), queue.tail.toArrayLike(Buffer, "le", 8));
+    await program.methods
+      .requestWithdrawal((await pooledShares()).sub(startingShares))
+      .accounts({
+        userConfig: userConfigPDA,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        withdrawalQueue: withdrawalQueuePDA,
+        statsShard: statsShardPDA,
+        ticket: ticketPDA,
+        user: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    assert.isTrue((await pooledShares()).eq(startingShares));
+
+    const balanceBefore = await provider.connection.getBalance(admin.publicKey);
+    await program.methods
+      .claimWithdrawal()
+      .accounts({
+        treasuryConfig: treasuryConfigPDA,
+        treasury: treasuryPDA,
+        sharePool: sharePoolPDA,
+        shareVault: shareVaultPDA,
+        withdrawalQueue: withdrawalQueuePDA,
+        ticket: ticketPDA,
+        owner: admin.publicKey,
+        ownerConfig: userConfigPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    assert.isNull(await provider.connection.getAccountInfo(ticketPDA));
+    assert.isAbove(await provider.connection.getBalance(admin.publicKey), balanceBefore);
+    const after = await program.account.withdrawalQueue.fetch(withdrawalQueuePDA);
+    assert.isTrue(after.head.eq(after.tail));
+  });
+
+  it("Queues share tokens and holds a frozen holder's ticket until it is cancelled", async () => {
+    const holder = await createUser();
+    const { userConfig } = userAccounts(holder.publicKey);
+    const holderShares = getAssociatedTokenAddressSync(shareMintPDA, holder.publicKey);
+    await program.methods
+      .depositShares(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({
+        ...poolAccounts,
+        userConfig,
+        userShares: holderShares,
+        user: holder.publicKey,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([holder])
+      .rpc();
+    const shares = (await getAccount(provider.connection, holderShares)).amount;
+    const activityBefore = (await program.account.userConfig.fetch(userConfig)).lastActivityTs;
+    await waitForClusterTime(activityBefore.toNumber() + 2);
+
+    const id = (await program.account.withdrawalQueue.fetch(withdrawalQueuePDA)).tail;
+    await program.methods
+      .requestShareWithdrawal(new anchor.BN(shares.toString()))
+      .accounts({
+        userConfig,
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        userShares: holderShares,
+        withdrawalQueue: withdrawalQueuePDA,
+        ticket: ticketAt(id),
+        user: holder.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([holder])
+      .rpc();
+    assert.equal((await getAccount(provider.connection, holderShares)).amount.toString(), "0");
+    const activityAfter = (await program.account.userConfig.fetch(userConfig)).lastActivityTs;
+    assert.isTrue(activityAfter.gt(activityBefore));
+
+    const manageAccounts = manageAccountsFor(holder.publicKey);
+    await program.methods.freezeUser(admin.publicKey).accounts(manageAccounts).rpc();
+    await expectError(claimTicket(id, holder.publicKey), "AccountFrozen");
+
+    // A risk manager hands the shares back so the queue isn't stuck behind them
+    await program.methods
+      .cancelWithdrawal()
+      .accounts({
+        sharePool: sharePoolPDA,
+        shareMint: shareMintPDA,
+        ticket: ticketAt(id),
+        owner: holder.publicKey,
+        ownerConfig: null,
+        ownerShares: holderShares,
+        roles: rolesPDA,
+        authority: admin.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const returned = await getAccount(provider.connection, holderShares);
+    assert.notEqual(returned.amount.toString(), "0");
+    assert.isTrue(returned.isFrozen);
+    await claimTicket(id, holder.publicKey);
+    await program.methods.unfreezeUser().accounts(manageAccounts).rpc();
+  });
+
+  it("Never unlocks a later ticket before an earlier one and lets owners cancel", async () => {
+    const startingShares = await pooledShares();
+    await program.methods
+      .depositPooled(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts(pooledAccounts)
+      .rpc();
+    const half = (await pooledShares()).sub(startingShares).divn(2);
+    const requestAccounts = (id: anchor.BN) => ({
+      userConfig: userConfigPDA,
+      sharePool: sharePoolPDA,
+      shareMint: shareMintPDA,
+      withdrawalQueue: withdrawalQueuePDA,
+      statsShard: statsShardPDA,
+      ticket: ticketAt(id),
+      user: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const first = (await program.account.withdrawalQueue.fetch(withdrawalQueuePDA)).tail;
+    await setWithdrawalNotice(3600);
+    await program.methods.requestWithdrawal(half).accounts(requestAccounts(first)).rpc();
+    const second = first.addn(1);
+    await setWithdrawalNotice(0);
+    await program.methods.requestWithdrawal(half).accounts(requestAccounts(second)).rpc();
+
+    const earlier = await program.account.withdrawalTicket.fetch(ticketAt(first));
+    const later = await program.account.withdrawalTicket.fetch(ticketAt(second));
+    assert.isTrue(later.unlockTs.eq(earlier.unlockTs));
+    await expectError(claimTicket(first, admin.publicKey), "WithdrawalNotReady");
+
+    // Cancelling puts the position back and lets the queue move past the tickets
+    for (const id of [first, second]) {
+      await program.methods
+        .cancelWithdrawal()
+        .accounts({
+          sharePool: sharePoolPDA,
+          shareMint: shareMintPDA,
+          ticket: ticketAt(id),
+          owner: admin.publicKey,
+          ownerConfig: userConfigPDA,
+          ownerShares: null,
+          roles: rolesPDA,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+    assert.isTrue((await pooledShares()).gt(startingShares));
+    await claimTicket(first, admin.publicKey);
+    await claimTicket(second, admin.publicKey);
+    const queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePDA);
+    assert.isTrue(queue.head.eq(queue.tail));
+  });
+});
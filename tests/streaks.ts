@@ -0,0 +1,36 @@
+import { Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import { createUser, deposit, program, setupProtocol, userAccounts } from "./fixture";
+
+describe("streaks", () => {
+  const POINTS_PER_STREAK_PERIOD = 10;
+
+  let user: Keypair;
+
+  const userConfig = () => program.account.userConfig.fetch(userAccounts(user.publicKey).userConfig);
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser();
+  });
+
+  it("Ignores deposits below the qualifying minimum", async () => {
+    await deposit(0.05 * LAMPORTS_PER_SOL, user);
+    const { streak, rewardPoints } = await userConfig();
+    assert.equal(streak, 0);
+    assert.isTrue(rewardPoints.eqn(0));
+  });
+
+  it("Starts a streak and earns points once per period", async () => {
+    await deposit(0.2 * LAMPORTS_PER_SOL, user);
+    let config = await userConfig();
+    assert.equal(config.streak, 1);
+    assert.isTrue(config.rewardPoints.eqn(POINTS_PER_STREAK_PERIOD));
+
+    // A second qualifying deposit in the same period earns nothing more
+    await deposit(0.2 * LAMPORTS_PER_SOL, user);
+    config = await userConfig();
+    assert.equal(config.streak, 1);
+    assert.isTrue(config.rewardPoints.eqn(POINTS_PER_STREAK_PERIOD));
+  });
+});
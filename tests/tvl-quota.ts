@@ -0,0 +1,60 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  aggregateStats,
+  createUser,
+  deposit,
+  expectError,
+  program,
+  resetGlobalState,
+  setupProtocol,
+  treasuryConfigPDA,
+  updateTvlCap,
+  userAccounts,
+} from "./fixture";
+
+describe("tvl quota", () => {
+  let user: Keypair;
+
+  // Lamports the user's stats shard can still take before the next crank
+  const headroom = async () => {
+    const shard = await program.account.statsShard.fetch(userAccounts(user.publicKey).statsShard);
+    return shard.tvlQuota.sub(shard.totalTvl).toNumber();
+  };
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser(30 * LAMPORTS_PER_SOL);
+  });
+
+  after(async () => {
+    await resetGlobalState();
+  });
+
+  it("Counts every slot against the global cap", async () => {
+    await aggregateStats();
+    const { totalTvl } = await program.account.treasuryConfig.fetch(treasuryConfigPDA);
+
+    // Leave 9 SOL under the cap, split between the shards and the pool
+    await updateTvlCap(totalTvl.add(new anchor.BN(9 * LAMPORTS_PER_SOL)).toNumber());
+    await aggregateStats();
+
+    const available = await headroom();
+    assert.isAtMost(available, 9 * LAMPORTS_PER_SOL / 8);
+    await expectError(deposit(2 * available, user), "TvlCapExceeded");
+  });
+
+  it("Lets a busy shard keep growing once the crank hands out fresh quota", async () => {
+    const half = Math.floor((await headroom()) / 2);
+    await deposit(half, user);
+    await expectError(deposit(half * 2, user), "TvlCapExceeded");
+
+    await aggregateStats();
+    assert.isAbove(await headroom(), half);
+    await deposit(half, user);
+
+    const config = await program.account.treasuryConfig.fetch(treasuryConfigPDA);
+    assert.isTrue(config.totalTvl.lte(config.tvlCap));
+  });
+});
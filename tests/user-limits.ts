@@ -0,0 +1,97 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+import {
+  STATS_SHARD_COUNT,
+  admin,
+  createUser,
+  deposit,
+  expectError,
+  pda,
+  program,
+  rolesPDA,
+  setupProtocol,
+  statsShardPDA,
+  treasuryConfigPDA,
+  treasuryPDA,
+  userAccounts,
+  waitForClusterTime,
+} from "./fixture";
+
+describe("user limits", () => {
+  const DAILY_LIMIT = LAMPORTS_PER_SOL;
+
+  let user: Keypair;
+
+  const userConfig = () => program.account.userConfig.fetch(userAccounts(user.publicKey).userConfig);
+
+  const jointDeposit = (lamports: number) => {
+    const jointVault = pda(
+      Buffer.from("joint_vault"),
+      user.publicKey.toBuffer(),
+      new anchor.BN(1).toArrayLike(Buffer, "le", 8)
+    );
+    return program.methods
+      .jointDeposit(new anchor.BN(lamports))
+      .accounts({
+        jointVault,
+        funds: pda(Buffer.from("joint_vault_funds"), jointVault.toBuffer()),
+        memberConfig: userAccounts(user.publicKey).userConfig,
+        treasuryConfig: treasuryConfigPDA,
+        statsShard: statsShardPDA(jointVault.toBuffer()[0] % STATS_SHARD_COUNT),
+        treasury: treasuryPDA,
+        member: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+  };
+
+  before(async () => {
+    await setupProtocol();
+    user = await createUser();
+
+    await program.methods
+      .setUserLimits(null, new anchor.BN(DAILY_LIMIT))
+      .accounts({
+        roles: rolesPDA,
+        userConfig: userAccounts(user.publicKey).userConfig,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const jointVault = pda(
+      Buffer.from("joint_vault"),
+      user.publicKey.toBuffer(),
+      new anchor.BN(1).toArrayLike(Buffer, "le", 8)
+    );
+    await program.methods
+      .createJointVault(new anchor.BN(1), [user.publicKey, Keypair.generate().publicKey], { anyMember: {} })
+      .accounts({
+        jointVault,
+        funds: pda(Buffer.from("joint_vault_funds"), jointVault.toBuffer()),
+        creator: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+  });
+
+  it("Counts joint deposits against the same daily limit", async () => {
+    await deposit(0.6 * LAMPORTS_PER_SOL, user);
+    await expectError(jointDeposit(0.6 * LAMPORTS_PER_SOL), "UserLimitExceeded");
+    await expectError(deposit(0.6 * LAMPORTS_PER_SOL, user), "UserLimitExceeded");
+  });
+
+  it("Lets earlier deposits decay out of the window instead of resetting it", async () => {
+    const before = await userConfig();
+    await waitForClusterTime(before.depositWindowTs.toNumber() + 2);
+
+    const amount = 0.1 * LAMPORTS_PER_SOL;
+    await jointDeposit(amount);
+    const after = await userConfig();
+    assert.isTrue(after.depositWindowTs.gt(before.depositWindowTs));
+    assert.isTrue(after.depositedInWindow.lt(before.depositedInWindow.addn(amount)));
+    assert.isTrue(after.depositedInWindow.gt(before.depositedInWindow));
+  });
+});